@@ -1,7 +1,7 @@
 use bevy::{asset::{AssetServer, Assets, Handle}, audio::AudioSource, ecs::{entity::Entity, query::With, schedule::NextState, system::{Commands, Query, Res, ResMut, Resource}}, render::texture::Image, time::{Timer, TimerMode}};
 
 use crate::{
-    v_components::MainMenuEntity, v_config::SIMULATION_RATE, v_hotbar::FadeTimer, v_lib::VoxelInfo, v_lighting::SunDirection, v_main_menu::{clear_main_menu_entities}, v_plugins::SpeedBar, v_selector::VoxelSelector, v_simulation::MyTimer, v_structure::Voxel, AppState
+    v_components::MainMenuEntity, v_config::SIMULATION_RATE, v_hotbar::FadeTimer, v_lib::VoxelInfo, v_lighting::SunDirection, v_probe::ProbeRecorder, v_main_menu::{clear_main_menu_entities}, v_plugins::SpeedBar, v_selector::VoxelSelector, v_simulation::{MyTimer, SimulationController, SimulationHistory}, v_structure::Voxel, v_voxel_settings::VoxelSettingsPanel, AppState
 };
 use std::time::Duration;
 
@@ -37,6 +37,10 @@ pub fn voxel_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(SunDirection::new());
     commands.insert_resource(FadeTimer::new());
     commands.insert_resource(SpeedBar::new());
+    commands.insert_resource(SimulationController::new());
+    commands.insert_resource(SimulationHistory::new());
+    commands.insert_resource(ProbeRecorder::new());
+    commands.insert_resource(VoxelSettingsPanel::new());
 }
 
 fn load_textures(asset_server: &Res<AssetServer>) -> TextureHandles {
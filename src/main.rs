@@ -8,8 +8,11 @@ use bevy_fps_controller::controller::*;
 use bevy_rapier3d::{plugin::RapierConfiguration, prelude::*};
 mod a_loading;
 mod b_voxel_setup;
+mod v_circuit;
 mod v_components;
 mod v_config;
+mod v_diagnostics;
+mod v_display;
 mod v_graphics;
 mod v_graphics_helper;
 mod v_hotbar;
@@ -17,31 +20,46 @@ mod v_in_game_menu;
 mod v_lib;
 mod v_lighting;
 mod v_main_menu;
+mod v_memory;
+mod v_netlist;
+mod v_palette;
 mod v_player2;
 mod v_pre_main_menu;
+mod v_probe;
 mod v_save;
 mod v_selector;
 mod v_settings;
 mod v_simulation;
 mod v_structure;
+mod v_timeline;
+mod v_timing;
+mod v_voxel_settings;
+mod v_waveform;
 mod v_plugins;
 use a_loading::{asset_check, voxel_loading};
 use b_voxel_setup::voxel_setup;
 use v_config::SUN_TIMER_RATE;
+use v_diagnostics::{diagnostics_panel, draw_diagnostic_gizmos};
+use v_display::{spawn_display_faces, update_display_faces};
 use v_graphics::update_voxel_emissive;
-use v_hotbar::{hotbar_ui, timer_update_system, voxel_descriptor};
+use v_hotbar::{hotbar_ui, timer_update_system, update_hotbar_page, voxel_descriptor, wire_color_picker};
 use v_in_game_menu::{in_game_menu};
-use v_lib::update_info;
+use v_lib::{draw_facing_gizmo, update_info};
 use v_lighting::{daylight_cycle, CycleTimer};
 use v_main_menu::{
     load_world_menu, main_menu_buttons, settings_menu, setup_main_menu, setup_world_naming, world_naming, SelectedWorld, WorldName
 };
+use v_palette::component_palette;
 use v_player2::{manage_cursor, player_setup, respawn, voxel_interaction_system};
 use v_plugins::WidgetPlugin;
+use v_probe::{draw_probe_gizmos, probe_input_system};
 use v_pre_main_menu::{pre_main_menu_cleanup, print_debug};
 use v_save::{autosave_system, check_for_save_input, world_loader, SaveEvent};
 use v_settings::{print_monitor_size, update_global_screen, GlobalSettings};
-use v_simulation::logic_operation_system;
+use v_simulation::{logic_operation_system, simulation_control_input};
+use v_timeline::timeline_panel;
+use v_voxel_settings::voxel_settings_panel;
+use v_waveform::waveform_panel;
 
 // Application state definitions
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -131,11 +149,31 @@ fn main() {
                 daylight_cycle,
                 check_for_save_input,
                 timer_update_system,
+                update_hotbar_page,
                 update_voxel_emissive,
+                simulation_control_input,
                 logic_operation_system,
                 autosave_system,
             )
                 .run_if(in_state(AppState::InGame)),
         )
+        .add_systems(
+            Update,
+            (
+                timeline_panel,
+                voxel_settings_panel,
+                probe_input_system,
+                draw_probe_gizmos,
+                waveform_panel,
+                diagnostics_panel,
+                draw_diagnostic_gizmos,
+                draw_facing_gizmo,
+                component_palette,
+                spawn_display_faces,
+                update_display_faces,
+                wire_color_picker,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
         .run();
 }
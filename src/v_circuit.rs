@@ -0,0 +1,1527 @@
+use bevy::math::IVec3;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use crate::v_components::{BusValue, Facing, Logic, StateVoxel, TypeVoxel, VoxelSettings};
+use crate::v_config::{BUS_MAX_WIDTH, CHANNEL_DEFAULT_NAME, DELAY_MAX_TICKS, OSCILLATION_MAX_GAP};
+use crate::v_netlist::{Gate, NetId, Netlist};
+use crate::v_timing::TimingModel;
+
+// Headless circuit model. Holds the same data as the voxel entities and applies the
+// same rules as the game, so circuits can be stepped without a window or an ECS world.
+// The netlist is compiled lazily, and voxels inserted, removed or rewired since the last step
+// are folded into it before the next one. Until a pending recompile happens, queries read the
+// last netlist compiled.
+#[derive(Default, Clone)]
+pub struct Circuit {
+    voxels: HashMap<IVec3, (TypeVoxel, StateVoxel)>,
+    settings: HashMap<IVec3, VoxelSettings>,
+    facings: HashMap<IVec3, Facing>,
+    values: HashMap<IVec3, u64>,
+    levels: HashMap<IVec3, Logic>,
+    memories: HashMap<IVec3, Arc<Vec<u64>>>,
+    memory_files: HashMap<IVec3, String>,
+    channels: HashMap<IVec3, String>,
+    netlist: Option<Netlist>,
+    recompile: bool,
+    events: EventQueue,
+    timing: TimingModel,
+    contention: ContentionPolicy,
+    tick: u64,
+    scheduled: BTreeMap<u64, Vec<Update>>,
+    manual_changes: Vec<StateChange>,
+    edits: Vec<IVec3>,
+    journal: Journal,
+    delta: TickDelta,
+}
+
+// How a net resolves when the Outs and tri-state buffers driving it disagree. Contended nets
+// are flagged whichever policy is chosen.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContentionPolicy {
+    WiredOr,
+    Error,
+    #[default]
+    LastDriver,
+}
+
+impl ContentionPolicy {
+    pub fn resolve(&self, driver_states: &[bool]) -> bool {
+        match self {
+            ContentionPolicy::WiredOr => driver_states.iter().any(|state| *state),
+            ContentionPolicy::Error => driver_states.iter().all(|state| *state),
+            ContentionPolicy::LastDriver => driver_states.last().copied().unwrap_or(false),
+        }
+    }
+
+    // Disabled tri-state drivers are left out, and a net with nothing else driving it floats.
+    // Under the Error policy disagreeing drivers leave the net undefined. Returns the level
+    // and whether the drivers disagreed.
+    pub fn resolve_levels(&self, driver_levels: &[Logic]) -> (Logic, bool) {
+        if driver_levels.contains(&Logic::Unknown) {
+            return (Logic::Unknown, false);
+        }
+        let driver_states: Vec<bool> = driver_levels
+            .iter()
+            .filter(|level| **level != Logic::HighZ)
+            .map(|level| *level == Logic::High)
+            .collect();
+        let Some(first) = driver_states.first() else {
+            return (Logic::HighZ, false);
+        };
+        let contended = driver_states.iter().any(|state| state != first);
+        match self {
+            ContentionPolicy::Error if contended => (Logic::Unknown, true),
+            _ => (Logic::from_bool(self.resolve(&driver_states)), contended),
+        }
+    }
+}
+
+// What a step changed besides voxel states, each entry with its value before and after:
+// updates in flight by due tick, component outputs and levels, and memory words. History
+// undoes or replays these instead of keeping a copy of the circuit per tick.
+#[derive(Default, Clone)]
+pub struct TickDelta {
+    scheduled: Vec<(u64, Vec<Update>, Vec<Update>)>,
+    values: Vec<(IVec3, Option<u64>, Option<u64>)>,
+    levels: Vec<(IVec3, Option<Logic>, Option<Logic>)>,
+    memories: Vec<(IVec3, usize, u64, u64)>,
+}
+
+// The value each entry had before it was first written since the last step ended, which
+// becomes that step's TickDelta.
+#[derive(Default, Clone)]
+struct Journal {
+    scheduled: BTreeMap<u64, Vec<Update>>,
+    values: HashMap<IVec3, Option<u64>>,
+    levels: HashMap<IVec3, Option<Logic>>,
+    memories: HashMap<(IVec3, usize), u64>,
+}
+
+// A voxel whose state flipped, with the state it had before, so history can undo it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StateChange {
+    pub position: IVec3,
+    pub previous: bool,
+    pub state: bool,
+}
+
+// A delayed write. When an Out or a Delay changes, each net it drives is resolved from its
+// drivers and the result lands after the net's wire delay. Arithmetic components write a
+// whole word with `Value`, and tri-state buffers their output level with `Level`.
+#[derive(Clone, Copy, PartialEq)]
+enum Update {
+    Voxel(IVec3, bool),
+    Value(IVec3, u64),
+    Level(IVec3, Logic),
+    Resolve(NetId),
+    Net(NetId, u64, Logic),
+}
+
+// Changes waiting to be seen by the next tick. Only gates reading a changed net, Outs
+// reading a changed gate or switch and components next to an edit get re-evaluated, unless
+// a full sweep was requested.
+#[derive(Default, Clone)]
+struct EventQueue {
+    full_sweep: bool,
+    nets: Vec<NetId>,
+    sources: Vec<IVec3>,
+    components: Vec<IVec3>,
+}
+
+impl Circuit {
+    pub fn new() -> Self {
+        Circuit::default()
+    }
+
+    pub fn insert(&mut self, position: IVec3, voxel_type: TypeVoxel, state: bool) {
+        self.voxels.insert(position, (voxel_type, StateVoxel(state)));
+        match VoxelSettings::default_for(voxel_type) {
+            Some(settings) => self.settings.insert(position, settings),
+            None => self.settings.remove(&position),
+        };
+        self.facings.remove(&position);
+        self.values.remove(&position);
+        self.levels.remove(&position);
+        self.memories.remove(&position);
+        self.memory_files.remove(&position);
+        match voxel_type {
+            TypeVoxel::Transmitter | TypeVoxel::Receiver => self.channels.insert(position, CHANNEL_DEFAULT_NAME.to_string()),
+            _ => self.channels.remove(&position),
+        };
+        self.edits.push(position);
+    }
+
+    pub fn remove(&mut self, position: IVec3) -> Option<(TypeVoxel, StateVoxel)> {
+        self.settings.remove(&position);
+        self.facings.remove(&position);
+        self.values.remove(&position);
+        self.levels.remove(&position);
+        self.memories.remove(&position);
+        self.memory_files.remove(&position);
+        self.channels.remove(&position);
+        let removed = self.voxels.remove(&position);
+        if removed.is_some() {
+            self.edits.push(position);
+        }
+        removed
+    }
+
+    pub fn get(&self, position: IVec3) -> Option<(TypeVoxel, StateVoxel)> {
+        self.voxels.get(&position).copied()
+    }
+
+    pub fn state(&self, position: IVec3) -> Option<bool> {
+        self.voxels.get(&position).map(|(_, state)| state.0)
+    }
+
+    // Only gates take a facing.
+    pub fn set_facing(&mut self, position: IVec3, facing: Facing) {
+        if self.voxels.get(&position).map_or(false, |(voxel_type, _)| voxel_type.is_gate()) {
+            self.facings.insert(position, facing);
+            self.edits.push(position);
+        }
+    }
+
+    pub fn facing(&self, position: IVec3) -> Option<Facing> {
+        self.facings.get(&position).copied()
+    }
+
+    pub fn all_facings(&self) -> impl Iterator<Item = (IVec3, Facing)> + '_ {
+        self.facings.iter().map(|(position, facing)| (*position, *facing))
+    }
+
+    pub fn settings(&self, position: IVec3) -> Option<VoxelSettings> {
+        self.settings.get(&position).copied()
+    }
+
+    pub fn all_settings(&self) -> impl Iterator<Item = (IVec3, VoxelSettings)> + '_ {
+        self.settings.iter().map(|(position, settings)| (*position, *settings))
+    }
+
+    // Only replaces settings of a voxel that already has settings of the same kind. Values
+    // out of range, e.g. from an edited save, are clamped.
+    pub fn set_settings(&mut self, position: IVec3, settings: VoxelSettings) {
+        let settings = settings.clamped();
+        if let Some(current) = self.settings.get_mut(&position) {
+            if std::mem::discriminant(current) == std::mem::discriminant(&settings) {
+                // Tap bits, word widths and wire colours are wired into the netlist.
+                let rewires = matches!(
+                    settings,
+                    VoxelSettings::Tap { .. } | VoxelSettings::Width { .. } | VoxelSettings::Memory { .. } | VoxelSettings::Wire { .. }
+                );
+                if rewires && *current != settings {
+                    self.edits.push(position);
+                }
+                *current = settings;
+            }
+        }
+    }
+
+    // Writes made from outside the simulation (a player flipping a switch) are reported
+    // again by the next step, so anything recording tick deltas sees them too.
+    pub fn set_state(&mut self, position: IVec3, new_state: bool) {
+        self.netlist();
+        match self.netlist.as_ref().and_then(|netlist| netlist.net_at(position)) {
+            Some(net) => {
+                let changes = self.set_net_state(net, new_state as u64, Logic::from_bool(new_state));
+                self.manual_changes.extend(changes);
+            }
+            None => {
+                if let Some(change) = self.set_voxel_state(position, new_state) {
+                    self.events.sources.push(position);
+                    self.manual_changes.push(change);
+                }
+            }
+        }
+    }
+
+    pub fn press_button(&mut self, position: IVec3) {
+        let Some(VoxelSettings::Button { hold_ticks }) = self.settings(position) else {
+            return;
+        };
+        self.set_state(position, true);
+        if hold_ticks > 0 {
+            // Clicking again restarts the hold time instead of keeping the earlier release.
+            self.retain_scheduled(|update| !matches!(update, Update::Voxel(voxel, false) if *voxel == position));
+            self.schedule(self.tick + hold_ticks as u64, Update::Voxel(position, false));
+        }
+    }
+
+    pub fn release_button(&mut self, position: IVec3) {
+        if let Some(VoxelSettings::Button { hold_ticks: 0 }) = self.settings(position) {
+            self.set_state(position, false);
+        }
+    }
+
+    // Steps a Delay through 1..=DELAY_MAX_TICKS, the in-world way of adjusting it.
+    pub fn cycle_delay(&mut self, position: IVec3) {
+        if let Some(VoxelSettings::Delay { ticks }) = self.settings.get_mut(&position) {
+            *ticks = (*ticks).clamp(1, DELAY_MAX_TICKS) % DELAY_MAX_TICKS + 1;
+        }
+    }
+
+    pub fn cycle_tap(&mut self, position: IVec3) {
+        if let Some(VoxelSettings::Tap { bit }) = self.settings.get(&position).copied() {
+            self.set_settings(position, VoxelSettings::Tap { bit: (bit + 1) % BUS_MAX_WIDTH });
+        }
+    }
+
+    // Replaces the contents of a ROM or RAM. Words past the end of its address range are
+    // kept but never read.
+    pub fn load_memory(&mut self, position: IVec3, words: Vec<u64>) {
+        if matches!(self.settings(position), Some(VoxelSettings::Memory { .. })) {
+            self.memories.insert(position, Arc::new(words));
+            self.events.components.push(position);
+        }
+    }
+
+    // The word a display or arithmetic component last computed, or the word on a bus voxel.
+    pub fn value(&self, position: IVec3) -> Option<u64> {
+        self.values.get(&position).copied()
+    }
+
+    pub fn all_values(&self) -> impl Iterator<Item = (IVec3, u64)> + '_ {
+        self.values.iter().map(|(position, value)| (*position, *value))
+    }
+
+    // Puts back a word from a save. The next step re-evaluates everything around it.
+    pub fn restore_value(&mut self, position: IVec3, value: u64) {
+        if self.voxels.contains_key(&position) {
+            self.set_value(position, value);
+            self.recompile = true;
+        }
+    }
+
+    // Also holds the rows of a Screen.
+    pub fn memory(&self, position: IVec3) -> Option<&[u64]> {
+        self.memories.get(&position).map(|words| words.as_slice())
+    }
+
+    // The file in the world folder a ROM or RAM is loaded from.
+    pub fn set_memory_file(&mut self, position: IVec3, file_name: String) {
+        if matches!(self.settings(position), Some(VoxelSettings::Memory { .. })) {
+            self.memory_files.insert(position, file_name);
+        }
+    }
+
+    pub fn memory_file(&self, position: IVec3) -> Option<&str> {
+        self.memory_files.get(&position).map(|file_name| file_name.as_str())
+    }
+
+    pub fn all_memory_files(&self) -> impl Iterator<Item = (IVec3, &str)> + '_ {
+        self.memory_files.iter().map(|(position, file_name)| (*position, file_name.as_str()))
+    }
+
+    // The channel a Transmitter or Receiver is on.
+    pub fn channel(&self, position: IVec3) -> Option<&str> {
+        self.channels.get(&position).map(|channel| channel.as_str())
+    }
+
+    pub fn set_channel(&mut self, position: IVec3, channel: String) {
+        if let Some(current) = self.channels.get_mut(&position) {
+            if *current != channel {
+                *current = channel;
+                self.edits.push(position);
+            }
+        }
+    }
+
+    pub fn all_channels(&self) -> impl Iterator<Item = (IVec3, &str)> + '_ {
+        self.channels.iter().map(|(position, channel)| (*position, channel.as_str()))
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn timing(&self) -> &TimingModel {
+        &self.timing
+    }
+
+    // Delays are read as updates are scheduled, so the netlist stays as it is.
+    pub fn set_timing(&mut self, timing: TimingModel) {
+        self.timing = timing;
+    }
+
+    pub fn contention(&self) -> ContentionPolicy {
+        self.contention
+    }
+
+    pub fn set_contention(&mut self, contention: ContentionPolicy) {
+        if self.contention != contention {
+            self.contention = contention;
+            self.recompile = true;
+        }
+    }
+
+    pub fn bus_value(&self, position: IVec3) -> Option<BusValue> {
+        let netlist = self.netlist.as_ref()?;
+        let net = netlist.net_at(position).filter(|net| netlist.is_bus(*net))?;
+        Some(BusValue { width: netlist.net_widths[net], bits: netlist.net_states[net] })
+    }
+
+    // The level of a wire's net or a tri-state buffer's output; other voxels are 0 or 1.
+    pub fn level(&self, position: IVec3) -> Option<Logic> {
+        if let Some(level) = self.levels.get(&position) {
+            return Some(*level);
+        }
+        let net_level = self
+            .netlist
+            .as_ref()
+            .and_then(|netlist| netlist.net_at(position).map(|net| netlist.net_levels[net]));
+        net_level.or_else(|| self.state(position).map(Logic::from_bool))
+    }
+
+    pub fn is_contended(&self, position: IVec3) -> bool {
+        self.netlist.as_ref().map_or(false, |netlist| {
+            netlist.net_at(position).map_or(false, |net| netlist.net_contended[net])
+        })
+    }
+
+    pub fn contended_nets(&self) -> Vec<Vec<IVec3>> {
+        let Some(netlist) = self.netlist.as_ref() else {
+            return Vec::new();
+        };
+        netlist
+            .net_contended
+            .iter()
+            .enumerate()
+            .filter(|(_, contended)| **contended)
+            .map(|(net, _)| netlist.nets[net].clone())
+            .collect()
+    }
+
+    pub fn netlist(&mut self) -> &Netlist {
+        if self.netlist.is_none() || self.recompile {
+            self.events = EventQueue { full_sweep: true, ..Default::default() };
+            // Net and Out indices do not survive a recompile; the full sweep re-resolves them.
+            self.retain_scheduled(|update| matches!(update, Update::Voxel(..) | Update::Value(..) | Update::Level(..)));
+
+            let netlist = Netlist::compile(&self.voxels, &self.facings, &self.settings, &self.channels, &self.values);
+            self.sync_wires(&netlist);
+            self.netlist = Some(netlist);
+            self.recompile = false;
+            self.edits.clear();
+        }
+        self.apply_edits();
+        self.netlist.get_or_insert_with(Netlist::default)
+    }
+
+    // Folds the voxels placed, removed or rewired since the last step into the netlist.
+    // Components around them are re-evaluated and the nets they touch resolved again, while
+    // updates in flight on every other net still land.
+    fn apply_edits(&mut self) {
+        let Some(netlist) = self.netlist.as_mut() else {
+            return;
+        };
+        if self.edits.is_empty() {
+            return;
+        }
+        let edits = std::mem::take(&mut self.edits);
+        let update = netlist.update(&edits, &self.voxels, &self.facings, &self.settings, &self.channels, &self.values);
+
+        self.retain_scheduled(|scheduled| match scheduled {
+            Update::Resolve(net) | Update::Net(net, ..) => !update.dropped.contains(net),
+            _ => true,
+        });
+        self.events.nets.retain(|net| !update.dropped.contains(net));
+        self.events.nets.extend(update.created.iter().copied());
+        self.events.components.extend(update.components.iter().copied());
+        for net in update.created.iter().chain(update.driven.iter()) {
+            self.schedule(self.tick + 1, Update::Resolve(*net));
+        }
+        for net in update.created {
+            self.sync_net(net);
+        }
+    }
+
+    // Wires joining a live net pick up its value, e.g. a wire placed next to a powered one.
+    fn sync_wires(&mut self, netlist: &Netlist) {
+        for (wires, net_state) in netlist.nets.iter().zip(netlist.net_states.iter()) {
+            for wire in wires {
+                if let Some(change) = self.set_voxel_state(*wire, *net_state != 0) {
+                    self.manual_changes.push(change);
+                }
+            }
+        }
+    }
+
+    fn sync_net(&mut self, net: NetId) {
+        let Some(netlist) = self.netlist.as_ref() else {
+            return;
+        };
+        let (wires, state, is_bus) = (netlist.nets[net].clone(), netlist.net_states[net], netlist.net_buses[net]);
+        for wire in wires {
+            if is_bus {
+                self.set_value(wire, state);
+            }
+            if let Some(change) = self.set_voxel_state(wire, state != 0) {
+                self.manual_changes.push(change);
+            }
+        }
+    }
+
+    // What the last step changed besides voxel states, for history to record.
+    pub fn delta(&self) -> &TickDelta {
+        &self.delta
+    }
+
+    // Moves the circuit back (or forward) to a recorded tick, with the voxel writes and the
+    // deltas of every step in between, newest first when going back. Pulses and releases in
+    // flight then land as they would have, and the next step re-evaluates everything from the
+    // restored states. Outputs and memories of components removed since are left out. The
+    // netlist is compiled again right away, so levels and diagnostics match the restored tick.
+    pub fn rewind(&mut self, tick: u64, states: &[(IVec3, bool)], deltas: &[&TickDelta]) {
+        for (position, new_state) in states {
+            if let Some((_, state)) = self.voxels.get_mut(position) {
+                state.0 = *new_state;
+            }
+        }
+        let backwards = tick < self.tick;
+        for delta in deltas {
+            for (due, before, after) in delta.scheduled.iter() {
+                match if backwards { before } else { after } {
+                    updates if updates.is_empty() => self.scheduled.remove(due),
+                    updates => self.scheduled.insert(*due, updates.clone()),
+                };
+            }
+            for (position, before, after) in delta.values.iter() {
+                match if backwards { before } else { after } {
+                    Some(value) if self.voxels.contains_key(position) => self.values.insert(*position, *value),
+                    _ => self.values.remove(position),
+                };
+            }
+            for (position, before, after) in delta.levels.iter() {
+                match if backwards { before } else { after } {
+                    Some(level) if self.voxels.contains_key(position) => self.levels.insert(*position, *level),
+                    _ => self.levels.remove(position),
+                };
+            }
+            for (position, address, before, after) in delta.memories.iter() {
+                if let Some(memory) = self.memories.get_mut(position) {
+                    write_word(Arc::make_mut(memory), *address, if backwards { *before } else { *after });
+                }
+            }
+        }
+        self.tick = tick;
+        self.journal = Journal::default();
+        self.recompile = true;
+        self.netlist();
+    }
+
+    fn schedule(&mut self, due: u64, update: Update) {
+        self.journal.scheduled.entry(due).or_insert_with(|| self.scheduled.get(&due).cloned().unwrap_or_default());
+        self.scheduled.entry(due).or_default().push(update);
+    }
+
+    fn retain_scheduled(&mut self, keep: impl Fn(&Update) -> bool) {
+        for (due, updates) in self.scheduled.iter_mut() {
+            if !updates.iter().all(&keep) {
+                self.journal.scheduled.entry(*due).or_insert_with(|| updates.clone());
+                updates.retain(&keep);
+            }
+        }
+        self.scheduled.retain(|_, updates| !updates.is_empty());
+    }
+
+    // Returns whether the value changed.
+    fn set_value(&mut self, position: IVec3, value: u64) -> bool {
+        let previous = self.values.insert(position, value);
+        self.journal.values.entry(position).or_insert(previous);
+        previous != Some(value)
+    }
+
+    fn set_level(&mut self, position: IVec3, level: Logic) -> bool {
+        let previous = self.levels.insert(position, level);
+        self.journal.levels.entry(position).or_insert(previous);
+        previous != Some(level)
+    }
+
+    // Ends the step's delta with what each journalled entry holds now, leaving out entries
+    // that were written back to where they started.
+    fn close_delta(&mut self) {
+        let journal = std::mem::take(&mut self.journal);
+        self.delta = TickDelta {
+            scheduled: journal
+                .scheduled
+                .into_iter()
+                .map(|(due, before)| (due, before, self.scheduled.get(&due).cloned().unwrap_or_default()))
+                .filter(|(_, before, after)| before != after)
+                .collect(),
+            values: journal
+                .values
+                .into_iter()
+                .map(|(position, before)| (position, before, self.values.get(&position).copied()))
+                .filter(|(_, before, after)| before != after)
+                .collect(),
+            levels: journal
+                .levels
+                .into_iter()
+                .map(|(position, before)| (position, before, self.levels.get(&position).copied()))
+                .filter(|(_, before, after)| before != after)
+                .collect(),
+            memories: journal
+                .memories
+                .into_iter()
+                .map(|((position, address), before)| {
+                    let after = self.memories.get(&position).and_then(|memory| memory.get(address)).copied().unwrap_or(0);
+                    (position, address, before, after)
+                })
+                .filter(|(_, _, before, after)| before != after)
+                .collect(),
+        };
+    }
+
+    // True once nothing is waiting to be evaluated or applied, so further ticks change nothing.
+    // A circuit with a clock in it never settles.
+    pub fn is_stable(&self) -> bool {
+        self.netlist.as_ref().map_or(false, |netlist| netlist.clocks.is_empty())
+            && !self.events.full_sweep
+            && self.events.nets.is_empty()
+            && self.events.sources.is_empty()
+            && self.events.components.is_empty()
+            && self.edits.is_empty()
+            && self.scheduled.is_empty()
+    }
+
+    // Nets on a combinational loop that have flipped at least `toggles` times in a row, never
+    // more than OSCILLATION_MAX_GAP ticks apart, and are still flipping. Nets outside a loop
+    // only follow their drivers, so a fast Clock does not count.
+    pub fn oscillating_nets(&self, toggles: u32) -> Vec<Vec<IVec3>> {
+        let Some(netlist) = self.netlist.as_ref() else {
+            return Vec::new();
+        };
+        netlist
+            .net_toggles
+            .iter()
+            .enumerate()
+            .filter(|(net, net_toggles)| {
+                netlist.net_in_loop[*net]
+                    && net_toggles.streak >= toggles
+                    && net_toggles.last_tick + OSCILLATION_MAX_GAP >= self.tick
+            })
+            .map(|(net, _)| netlist.nets[net].clone())
+            .collect()
+    }
+
+    pub fn combinational_loops(&self) -> &[Vec<IVec3>] {
+        self.netlist
+            .as_ref()
+            .map_or(&[], |netlist| netlist.combinational_loops.as_slice())
+    }
+
+    // Positions whose state differs between the two circuits.
+    pub fn mismatches(&self, other: &Circuit) -> Vec<IVec3> {
+        self.voxels
+            .iter()
+            .filter(|(position, (_, state))| other.state(**position) != Some(state.0))
+            .map(|(position, _)| *position)
+            .collect()
+    }
+
+    // Advances the circuit by one tick and returns the voxels whose state changed.
+    // Gates and Outs read the previous tick's values, so evaluation order does not matter.
+    pub fn step(&mut self) -> Vec<StateChange> {
+        self.netlist();
+        let events = std::mem::take(&mut self.events);
+        let Some(netlist) = self.netlist.as_ref() else {
+            return Vec::new();
+        };
+
+        let (gates, outs) = if events.full_sweep {
+            ((0..netlist.gates.len()).collect(), (0..netlist.outs.len()).collect())
+        } else {
+            let gates = events
+                .nets
+                .iter()
+                .flat_map(|net| netlist.net_fanout[*net].iter().copied())
+                .chain(events.components.iter().filter_map(|position| netlist.gate_index.get(position).copied()));
+            let outs = events
+                .sources
+                .iter()
+                .filter_map(|source| netlist.source_fanout.get(source))
+                .flat_map(|fanout| fanout.iter().copied())
+                .chain(events.components.iter().filter_map(|position| netlist.out_index.get(position).copied()));
+            (sorted_unique(gates), sorted_unique(outs))
+        };
+
+        let mut changes = std::mem::take(&mut self.manual_changes);
+        changes.extend(self.evaluate(&gates, &outs, events.full_sweep));
+        self.close_delta();
+        changes
+    }
+
+    // Re-evaluates every gate and Out regardless of what changed. Used as the reference
+    // the event-driven step is checked against.
+    pub fn step_full_sweep(&mut self) -> Vec<StateChange> {
+        self.netlist();
+        self.events.full_sweep = true;
+        self.step()
+    }
+
+    fn evaluate(&mut self, gates: &[usize], outs: &[usize], full_sweep: bool) -> Vec<StateChange> {
+        self.tick += 1;
+        let Some(netlist) = self.netlist.as_ref() else {
+            return Vec::new();
+        };
+
+        // Clocks follow the tick counter and are re-evaluated on every tick.
+        let mut updates: Vec<(u64, Update)> = netlist
+            .clocks
+            .iter()
+            .filter_map(|position| {
+                let new_state = evaluate_clock(self.settings.get(position)?, self.tick);
+                Some((self.tick, Update::Voxel(*position, new_state)))
+            })
+            .collect();
+        let mut clocks_seen = Vec::new();
+        let mut memory_writes = Vec::new();
+        for index in gates {
+            let gate = &netlist.gates[*index];
+            let state = self.voxels[&gate.position].1 .0;
+            let last_clock = netlist.last_clocks[*index];
+            if let Some(net) = gate.clock {
+                clocks_seen.push((*index, netlist.net_states[net] != 0));
+            }
+            if gate.voxel_type.is_macro() {
+                let value = match self.settings.get(&gate.position) {
+                    Some(VoxelSettings::Memory { address_bits, .. }) => {
+                        let memory = self.memories.get(&gate.position).map_or(&[][..], |words| words.as_slice());
+                        let (value, write) = evaluate_memory(gate, &netlist.net_states, last_clock, *address_bits, memory);
+                        memory_writes.extend(write.map(|(address, data)| (gate.position, address, data)));
+                        value
+                    }
+                    Some(VoxelSettings::Screen { width, height }) => {
+                        let (value, write) = evaluate_screen(gate, &netlist.net_states, last_clock, *width, *height);
+                        memory_writes.extend(write.map(|(row, data)| (gate.position, row, data)));
+                        value
+                    }
+                    _ => evaluate_macro(gate, &netlist.net_states),
+                };
+                let due = self.tick + self.timing.gate_delay(gate.voxel_type) - 1;
+                updates.push((due, Update::Value(gate.position, value)));
+                continue;
+            }
+            if gate.voxel_type.is_pulse() {
+                let clock = gate.clock.map_or(false, |net| netlist.net_states[net] != 0);
+                if pulse_triggered(gate.voxel_type, clock, last_clock, state) {
+                    let width = match self.settings.get(&gate.position) {
+                        Some(VoxelSettings::Pulse { ticks }) => (*ticks).max(1) as u64,
+                        _ => 1,
+                    };
+                    let due = self.tick + self.timing.gate_delay(gate.voxel_type) - 1;
+                    updates.push((due, Update::Voxel(gate.position, true)));
+                    updates.push((due + width, Update::Voxel(gate.position, false)));
+                }
+                continue;
+            }
+            if gate.voxel_type == TypeVoxel::TriState {
+                let level = evaluate_tri_state(gate, &netlist.net_levels);
+                let due = self.tick + self.timing.gate_delay(gate.voxel_type) - 1;
+                updates.push((due, Update::Level(gate.position, level)));
+                continue;
+            }
+            let Some(new_state) = evaluate_gate(gate, &netlist.net_states, state, last_clock) else {
+                continue;
+            };
+            let delay = match self.settings.get(&gate.position) {
+                Some(VoxelSettings::Delay { ticks }) => (*ticks).max(1) as u64,
+                _ => self.timing.gate_delay(gate.voxel_type),
+            };
+            updates.push((self.tick + delay - 1, Update::Voxel(gate.position, new_state)));
+        }
+
+        let out_due = self.tick + self.timing.gate_delay(TypeVoxel::Out) - 1;
+        for index in outs {
+            let out = &netlist.outs[*index];
+            let new_state = out.sources.iter().any(|source| self.voxels[source].1 .0);
+            updates.push((out_due, Update::Voxel(out.position, new_state)));
+        }
+
+        // A full sweep also re-resolves nets whose drivers did not change, which brings
+        // freshly compiled nets in line with their drivers.
+        if full_sweep {
+            let nets = (0..netlist.nets.len()).filter(|net| !netlist.net_drivers[*net].is_empty());
+            updates.extend(nets.map(|net| (out_due, Update::Resolve(net))));
+        }
+
+        if let Some(netlist) = self.netlist.as_mut() {
+            for (index, clock) in clocks_seen {
+                netlist.last_clocks[index] = clock;
+            }
+        }
+        for (position, address, data) in memory_writes {
+            let memory = Arc::make_mut(self.memories.entry(position).or_default());
+            let previous = memory.get(address).copied().unwrap_or(0);
+            self.journal.memories.entry((position, address)).or_insert(previous);
+            write_word(memory, address, data);
+        }
+        for (due, update) in updates {
+            self.schedule(due, update);
+        }
+        self.apply_due_updates()
+    }
+
+    fn apply_due_updates(&mut self) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+
+        while let Some((due, _)) = self.scheduled.first_key_value() {
+            let due = *due;
+            if due > self.tick {
+                break;
+            }
+            self.journal.scheduled.entry(due).or_insert_with(|| self.scheduled[&due].clone());
+            let mut resolves = Vec::new();
+            for update in self.scheduled.remove(&due).unwrap_or_default() {
+                match update {
+                    Update::Voxel(position, new_state) => {
+                        if let Some(change) = self.set_voxel_state(position, new_state) {
+                            self.events.sources.push(position);
+                            changes.push(change);
+                            let driven = self.netlist.as_ref().and_then(|netlist| netlist.driven_nets.get(&position));
+                            resolves.extend(driven.into_iter().flatten().copied().map(Update::Resolve));
+                        }
+                    }
+                    Update::Value(position, value) => {
+                        if !self.set_value(position, value) {
+                            continue;
+                        }
+                        changes.extend(self.set_voxel_state(position, value != 0));
+                        let driven = self.netlist.as_ref().and_then(|netlist| netlist.driven_nets.get(&position));
+                        resolves.extend(driven.into_iter().flatten().copied().map(Update::Resolve));
+                    }
+                    Update::Level(position, level) => {
+                        if !self.set_level(position, level) {
+                            continue;
+                        }
+                        changes.extend(self.set_voxel_state(position, level == Logic::High));
+                        let driven = self.netlist.as_ref().and_then(|netlist| netlist.driven_nets.get(&position));
+                        resolves.extend(driven.into_iter().flatten().copied().map(Update::Resolve));
+                    }
+                    // Drivers that disagree are resolved by the world's contention policy.
+                    // Each Merger on a bus sets its own bit, and a channel is on when any of
+                    // its Transmitters is.
+                    Update::Resolve(net) => {
+                        let Some(netlist) = self.netlist.as_ref() else {
+                            continue;
+                        };
+                        // A wire nothing drives keeps its value; a channel nothing sends on is off.
+                        let drivers = &netlist.net_drivers[net];
+                        if drivers.is_empty() && !netlist.is_channel(net) {
+                            continue;
+                        }
+                        let (new_state, level, contended) = if netlist.is_bus(net) || netlist.is_channel(net) {
+                            let bus = drivers
+                                .iter()
+                                .filter_map(|driver| self.driver_value(netlist, *driver, net))
+                                .fold(0, |bus, value| bus | value);
+                            (bus, Logic::from_bool(bus != 0), false)
+                        } else {
+                            let levels: Vec<Logic> = drivers
+                                .iter()
+                                .filter_map(|driver| match self.levels.get(driver) {
+                                    Some(level) => Some(*level),
+                                    None => self.driver_value(netlist, *driver, net).map(|value| Logic::from_bool(value & 1 == 1)),
+                                })
+                                .collect();
+                            let (level, contended) = self.contention.resolve_levels(&levels);
+                            ((level == Logic::High) as u64, level, contended)
+                        };
+                        let due = self.tick + self.timing.net_delay(netlist.nets[net].len());
+
+                        if let Some(netlist) = self.netlist.as_mut() {
+                            netlist.net_contended[net] = contended;
+                        }
+                        self.schedule(due, Update::Net(net, new_state, level));
+                    }
+                    Update::Net(net, new_state, level) => {
+                        changes.extend(self.set_net_state(net, new_state, level));
+                    }
+                }
+            }
+            // Resolve after every driver due this tick has landed.
+            for resolve in resolves {
+                self.schedule(self.tick, resolve);
+            }
+        }
+        changes
+    }
+
+    // The word a driver puts on one of its nets: an arithmetic component's output, or the
+    // driver's state moved to its tap bit.
+    fn driver_value(&self, netlist: &Netlist, driver: IVec3, net: NetId) -> Option<u64> {
+        let word = match self.values.get(&driver) {
+            Some(value) => *value,
+            None => (self.state(driver)? as u64) << netlist.tap_bits.get(&driver).copied().unwrap_or(0),
+        };
+        Some(word >> netlist.driver_offsets.get(&(driver, net)).copied().unwrap_or(0))
+    }
+
+    fn set_voxel_state(&mut self, position: IVec3, new_state: bool) -> Option<StateChange> {
+        match self.voxels.get_mut(&position) {
+            Some((_, state)) if state.0 != new_state => {
+                let previous = state.0;
+                state.0 = new_state;
+                Some(StateChange { position, previous, state: new_state })
+            }
+            _ => None,
+        }
+    }
+
+    // Only wires whose on/off state flips are reported. A change of level alone (0 to Z, say)
+    // or of bus bits that leaves the bus on needs no write; wires are drawn from their level.
+    fn set_net_state(&mut self, net: NetId, new_state: u64, level: Logic) -> Vec<StateChange> {
+        let Some(netlist) = self.netlist.as_mut() else {
+            return Vec::new();
+        };
+        if netlist.net_states[net] == new_state && netlist.net_levels[net] == level {
+            return Vec::new();
+        }
+
+        netlist.net_states[net] = new_state;
+        netlist.net_levels[net] = level;
+        // Bus words are kept on the voxels as well, so they outlive the netlist and get saved.
+        if netlist.net_buses[net] {
+            for bus in netlist.nets[net].iter() {
+                let previous = self.values.insert(*bus, new_state);
+                self.journal.values.entry(*bus).or_insert(previous);
+            }
+        }
+        let toggles = &mut netlist.net_toggles[net];
+        if self.tick != toggles.last_tick {
+            let gap = self.tick - toggles.last_tick;
+            toggles.streak = if gap <= OSCILLATION_MAX_GAP { toggles.streak + 1 } else { 1 };
+            toggles.last_tick = self.tick;
+        }
+        self.events.nets.push(net);
+        let is_on = new_state != 0;
+        netlist.nets[net]
+            .iter()
+            .filter_map(|wire| match self.voxels.get_mut(wire) {
+                Some((_, state)) if state.0 != is_on => {
+                    let previous = state.0;
+                    state.0 = is_on;
+                    Some(StateChange { position: *wire, previous, state: is_on })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn write_word(memory: &mut Vec<u64>, address: usize, word: u64) {
+    if memory.len() <= address {
+        memory.resize(address + 1, 0);
+    }
+    memory[address] = word;
+}
+
+fn sorted_unique(indices: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut indices: Vec<usize> = indices.collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+pub fn get_adjacent_positions(position: IVec3) -> [IVec3; 6] {
+    [
+        position + IVec3::new(1, 0, 0),
+        position + IVec3::new(-1, 0, 0),
+        position + IVec3::new(0, 1, 0),
+        position + IVec3::new(0, -1, 0),
+        position + IVec3::new(0, 0, 1),
+        position + IVec3::new(0, 0, -1),
+    ]
+}
+
+// Returns None when the gate holds its current state, so nothing is scheduled for it.
+// Edge-triggered types compare the clock with the value seen when they were last evaluated.
+fn evaluate_gate(gate: &Gate, net_states: &[u64], state: bool, last_clock: bool) -> Option<bool> {
+    let active_inputs = gate.inputs.iter().filter(|net| net_states[**net] != 0).count();
+    let total_inputs = gate.inputs.len();
+    let pin = |index: usize| gate.pins.get(index).copied().flatten().map_or(false, |net| net_states[net] != 0);
+    let clock = gate.clock.map_or(false, |net| net_states[net] != 0);
+    let rising_edge = clock && !last_clock;
+
+    match gate.voxel_type {
+        TypeVoxel::And => Some(active_inputs == total_inputs && total_inputs > 0),
+        TypeVoxel::Or => Some(active_inputs > 0),
+        TypeVoxel::Xor => Some(active_inputs == 1),
+        TypeVoxel::Not => Some(total_inputs == 1 && active_inputs == 0),
+        TypeVoxel::Nand => Some(active_inputs < total_inputs),
+        TypeVoxel::Nor => Some(active_inputs == 0 && total_inputs > 0),
+        TypeVoxel::Xnor => Some(active_inputs != 1 && total_inputs > 0),
+        TypeVoxel::Buffer => Some(total_inputs == 1 && active_inputs == 1),
+        TypeVoxel::Delay | TypeVoxel::Merger | TypeVoxel::Transmitter | TypeVoxel::Receiver => Some(active_inputs > 0),
+        TypeVoxel::Splitter => Some(gate.inputs.first().map_or(false, |bus| net_states[*bus] >> gate.bit & 1 == 1)),
+        TypeVoxel::DFlipFlop => process_d_flip_flop_logic(clock, active_inputs),
+        TypeVoxel::EdgeDFlipFlop => rising_edge.then_some(active_inputs > 0),
+        TypeVoxel::TFlipFlop => (rising_edge && active_inputs > 0).then_some(!state),
+        // Reset wins when both inputs are on.
+        TypeVoxel::SrLatch => match (pin(0), pin(1)) {
+            (_, true) => Some(false),
+            (true, false) => Some(true),
+            (false, false) => None,
+        },
+        TypeVoxel::JkFlipFlop => match (rising_edge, pin(0), pin(1)) {
+            (true, true, true) => Some(!state),
+            (true, true, false) => Some(true),
+            (true, false, true) => Some(false),
+            _ => None,
+        },
+        _ => Some(false),
+    }
+}
+
+// A TriState passes the data behind it while enabled from the top and floats while disabled.
+// Data or an enable that is neither 0 nor 1 makes its output undefined.
+fn evaluate_tri_state(gate: &Gate, net_levels: &[Logic]) -> Logic {
+    let pin = |index: usize| gate.pins.get(index).copied().flatten().map_or(Logic::Low, |net| net_levels[net]);
+    match (pin(1), pin(0)) {
+        (Logic::Low, _) => Logic::HighZ,
+        (Logic::High, data @ (Logic::Low | Logic::High)) => data,
+        _ => Logic::Unknown,
+    }
+}
+
+// Whether a pulse starts, from the input now and on the previous tick. A PulseExtender
+// ignores its input while its pulse is still on.
+fn pulse_triggered(voxel_type: TypeVoxel, input: bool, last_input: bool, state: bool) -> bool {
+    match voxel_type {
+        TypeVoxel::RisingEdge => input && !last_input,
+        TypeVoxel::FallingEdge => !input && last_input,
+        TypeVoxel::AnyEdge => input != last_input,
+        TypeVoxel::PulseExtender => input && !last_input && !state,
+        _ => false,
+    }
+}
+
+fn evaluate_clock(settings: &VoxelSettings, tick: u64) -> bool {
+    match *settings {
+        VoxelSettings::Clock { period, phase, duty } => {
+            let period = period.max(1) as u64;
+            (tick + phase as u64) % period < duty as u64
+        }
+        _ => false,
+    }
+}
+
+fn process_d_flip_flop_logic(signal: bool, data: usize) -> Option<bool> {
+    match (signal, data) {
+        (true, d) if d > 0 => Some(true),
+        (true, 0) => Some(false),
+        _ => None,
+    }
+}
+
+// The output word of an arithmetic component. Wires read as 0 or 1; an adder only adds the
+// low `width` bits of each operand.
+fn evaluate_macro(gate: &Gate, net_states: &[u64]) -> u64 {
+    let pin = |index: usize| gate.pins.get(index).copied().flatten().map_or(0, |net| net_states[net]);
+    let mask = low_bits(gate.width);
+
+    match gate.voxel_type {
+        TypeVoxel::HalfAdder | TypeVoxel::FullAdder | TypeVoxel::Adder => (pin(0) & mask) + (pin(1) & mask) + (pin(2) & 1),
+        TypeVoxel::Comparator => {
+            let (a, b) = (pin(0), pin(1));
+            (a == b) as u64 | ((a < b) as u64) << 1 | ((a > b) as u64) << 2
+        }
+        TypeVoxel::Mux2 => if pin(2) & 1 == 1 { pin(1) } else { pin(0) },
+        TypeVoxel::Mux4 => pin(0) >> ((pin(1) & 1) | (pin(2) & 1) << 1) & 1,
+        TypeVoxel::Decoder => 1 << (pin(0) & 7),
+        TypeVoxel::SevenSegment => pin(0) | (pin(1) & 1) << 1 | (pin(2) & 1) << 2 | (pin(3) & 1) << 3,
+        _ => 0,
+    }
+}
+
+// A ROM or RAM reads the word at its address. A RAM with write enable on stores its data
+// input on a rising clock and outputs the stored word, returned as the write to apply.
+fn evaluate_memory(
+    gate: &Gate,
+    net_states: &[u64],
+    last_clock: bool,
+    address_bits: u32,
+    memory: &[u64],
+) -> (u64, Option<(usize, u64)>) {
+    let pin = |index: usize| gate.pins.get(index).copied().flatten().map_or(0, |net| net_states[net]);
+    let address = (pin(0) & low_bits(address_bits)) as usize;
+    let clock = gate.clock.map_or(false, |net| net_states[net] != 0);
+
+    if gate.voxel_type == TypeVoxel::Ram && clock && !last_clock && pin(2) & 1 == 1 {
+        let data = pin(1) & low_bits(gate.width);
+        return (data, Some((address, data)));
+    }
+    (memory.get(address).copied().unwrap_or(0) & low_bits(gate.width), None)
+}
+
+// A Screen stores its row data in the row it is pointed at, on a rising clock or, without a
+// clock wire, whenever either input changes. Rows live in the same store as RAM words.
+fn evaluate_screen(gate: &Gate, net_states: &[u64], last_clock: bool, width: u32, height: u32) -> (u64, Option<(usize, u64)>) {
+    let pin = |index: usize| gate.pins.get(index).copied().flatten().map_or(0, |net| net_states[net]);
+    let (data, row) = (pin(0) & low_bits(width), pin(1));
+    let clock = gate.clock.map_or(false, |net| net_states[net] != 0);
+    let write = gate.clock.is_none() || (clock && !last_clock);
+
+    (data, (write && row < height as u64).then_some((row as usize, data)))
+}
+
+fn low_bits(bits: u32) -> u64 {
+    if bits >= 64 { u64::MAX } else { (1 << bits) - 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v_config::OSCILLATION_TOGGLES;
+
+    // A Switch, the Out it powers and a wire, laid out from `switch` towards `direction`.
+    // Returns the wire.
+    fn input(circuit: &mut Circuit, switch: IVec3, direction: IVec3, state: bool) -> IVec3 {
+        circuit.insert(switch, TypeVoxel::Switch, state);
+        circuit.insert(switch + direction, TypeVoxel::Out, false);
+        circuit.insert(switch + direction * 2, TypeVoxel::Wire, false);
+        switch + direction * 2
+    }
+
+    // An Out above `gate` and the wire above that, which follows the gate's output.
+    fn output(circuit: &mut Circuit, gate: IVec3) -> IVec3 {
+        circuit.insert(gate + IVec3::Y, TypeVoxel::Out, false);
+        circuit.insert(gate + IVec3::Y * 2, TypeVoxel::Wire, false);
+        gate + IVec3::Y * 2
+    }
+
+    fn settle(circuit: &mut Circuit) {
+        for _ in 0..100 {
+            circuit.step();
+            if circuit.is_stable() {
+                return;
+            }
+        }
+        panic!("circuit did not settle");
+    }
+
+    #[test]
+    fn wire_follows_switch() {
+        let mut circuit = Circuit::new();
+        let wire = input(&mut circuit, IVec3::ZERO, IVec3::X, true);
+        circuit.insert(wire + IVec3::X, TypeVoxel::Wire, false);
+        settle(&mut circuit);
+        assert_eq!(circuit.state(wire), Some(true));
+        assert_eq!(circuit.state(wire + IVec3::X), Some(true));
+
+        circuit.set_state(IVec3::ZERO, false);
+        settle(&mut circuit);
+        assert_eq!(circuit.state(wire), Some(false));
+        assert_eq!(circuit.state(wire + IVec3::X), Some(false));
+    }
+
+    #[test]
+    fn vias_connect_to_components_only_vertically() {
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, TypeVoxel::Switch, true);
+        circuit.insert(IVec3::X, TypeVoxel::Out, false);
+        circuit.insert(IVec3::X * 2, TypeVoxel::Via, false);
+        circuit.insert(IVec3::X + IVec3::Y, TypeVoxel::Via, false);
+        settle(&mut circuit);
+        assert_eq!(circuit.state(IVec3::X * 2), Some(false));
+        assert_eq!(circuit.state(IVec3::X + IVec3::Y), Some(true));
+    }
+
+    #[test]
+    fn and_gate_reads_its_wires() {
+        let mut circuit = Circuit::new();
+        let gate = IVec3::ZERO;
+        circuit.insert(gate, TypeVoxel::And, false);
+        input(&mut circuit, IVec3::new(-3, 0, 0), IVec3::X, true);
+        input(&mut circuit, IVec3::new(3, 0, 0), -IVec3::X, false);
+        let result = output(&mut circuit, gate);
+        settle(&mut circuit);
+        assert_eq!(circuit.state(gate), Some(false));
+        assert_eq!(circuit.state(result), Some(false));
+
+        circuit.set_state(IVec3::new(3, 0, 0), true);
+        settle(&mut circuit);
+        assert_eq!(circuit.state(gate), Some(true));
+        assert_eq!(circuit.state(result), Some(true));
+    }
+
+    #[test]
+    fn level_changes_report_no_toggles() {
+        let mut circuit = Circuit::new();
+        // Data behind (-X) and enable on top of a TriState with no facing.
+        input(&mut circuit, IVec3::new(-3, 0, 0), IVec3::X, false);
+        input(&mut circuit, IVec3::new(0, 3, 0), -IVec3::Y, true);
+        circuit.insert(IVec3::ZERO, TypeVoxel::TriState, false);
+        circuit.insert(IVec3::X, TypeVoxel::Wire, false);
+        settle(&mut circuit);
+        assert_eq!(circuit.level(IVec3::X), Some(Logic::Low));
+
+        circuit.set_state(IVec3::new(0, 3, 0), false);
+        let mut changes = Vec::new();
+        for _ in 0..10 {
+            changes.extend(circuit.step());
+        }
+        assert_eq!(circuit.level(IVec3::X), Some(Logic::HighZ));
+        assert!(!changes.iter().any(|change| change.position == IVec3::X));
+    }
+
+    #[test]
+    fn rewind_mid_pulse_ends_the_pulse() {
+        let mut circuit = Circuit::new();
+        let extender = IVec3::ZERO;
+        input(&mut circuit, IVec3::new(-3, 0, 0), IVec3::X, false);
+        circuit.insert(extender, TypeVoxel::PulseExtender, false);
+        settle(&mut circuit);
+        circuit.set_state(IVec3::new(-3, 0, 0), true);
+        while circuit.state(extender) != Some(true) {
+            circuit.step();
+        }
+        let tick = circuit.tick();
+        let mut changes = Vec::new();
+        let mut deltas = Vec::new();
+        while circuit.state(extender) != Some(false) {
+            changes.extend(circuit.step());
+            deltas.push(circuit.delta().clone());
+        }
+
+        let states: Vec<(IVec3, bool)> = changes.iter().rev().map(|change| (change.position, change.previous)).collect();
+        circuit.rewind(tick, &states, &deltas.iter().rev().collect::<Vec<_>>());
+        assert_eq!(circuit.state(extender), Some(true));
+        settle(&mut circuit);
+        assert_eq!(circuit.state(extender), Some(false));
+    }
+
+    #[test]
+    fn rewind_restores_memory_words_from_deltas() {
+        let mut circuit = Circuit::new();
+        let screen = IVec3::ZERO;
+        circuit.insert(screen, TypeVoxel::Screen, false);
+        input(&mut circuit, LEFT, IVec3::X, false);
+        settle(&mut circuit);
+        let tick = circuit.tick();
+
+        let mut deltas = Vec::new();
+        let mut states = Vec::new();
+        for on in [true, false, true] {
+            circuit.set_state(LEFT, on);
+            for _ in 0..10 {
+                states.extend(circuit.step());
+                deltas.push(circuit.delta().clone());
+                // Only the one row written shows up, not a copy of the screen.
+                assert!(deltas.last().unwrap().memories.len() <= 1);
+            }
+            assert_eq!(circuit.memory(screen).and_then(|rows| rows.first()).copied(), Some(on as u64));
+        }
+
+        let states: Vec<(IVec3, bool)> = states.iter().rev().map(|change| (change.position, change.previous)).collect();
+        circuit.rewind(tick, &states, &deltas.iter().rev().collect::<Vec<_>>());
+        assert_eq!(circuit.memory(screen).and_then(|rows| rows.first()).copied().unwrap_or(0), 0);
+        assert_eq!(circuit.state(LEFT), Some(false));
+    }
+
+    #[test]
+    fn out_of_range_taps_are_clamped() {
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, TypeVoxel::Merger, false);
+        circuit.insert(IVec3::new(0, 0, 3), TypeVoxel::Adder, false);
+        circuit.set_settings(IVec3::ZERO, VoxelSettings::Tap { bit: 200 });
+        circuit.set_settings(IVec3::new(0, 0, 3), VoxelSettings::Width { bits: 64 });
+        assert_eq!(circuit.settings(IVec3::ZERO), Some(VoxelSettings::Tap { bit: BUS_MAX_WIDTH - 1 }));
+        assert_eq!(circuit.settings(IVec3::new(0, 0, 3)), Some(VoxelSettings::Width { bits: BUS_MAX_WIDTH - 1 }));
+        circuit.insert(IVec3::X, TypeVoxel::Bus, false);
+        input(&mut circuit, IVec3::new(-3, 0, 0), IVec3::X, true);
+        settle(&mut circuit);
+        assert_eq!(circuit.bus_value(IVec3::X).map(|value| value.bits), Some(1 << (BUS_MAX_WIDTH - 1)));
+    }
+
+    // Steps the circuit for `ticks` ticks, flipping one of `switches` every few of them, and
+    // checks each event-driven step against a full sweep of the same circuit.
+    fn assert_matches_full_sweep(circuit: &mut Circuit, switches: &[IVec3], ticks: usize) {
+        for tick in 0..ticks {
+            if tick % 5 == 0 {
+                let switch = switches[tick / 5 % switches.len()];
+                circuit.set_state(switch, !circuit.state(switch).unwrap());
+            }
+            let mut reference = circuit.clone();
+            reference.step_full_sweep();
+            circuit.step();
+            assert_eq!(circuit.mismatches(&reference), Vec::new(), "tick {}", circuit.tick());
+        }
+    }
+
+    #[test]
+    fn event_driven_steps_match_full_sweeps() {
+        let mut circuit = Circuit::new();
+        // A Clock and the wire it drives.
+        let clock = IVec3::new(0, 0, 20);
+        circuit.insert(clock, TypeVoxel::Clock, false);
+        circuit.set_settings(clock, VoxelSettings::Clock { period: 3, phase: 0, duty: 1 });
+        circuit.insert(clock + IVec3::X, TypeVoxel::Out, false);
+        circuit.insert(clock + IVec3::X * 2, TypeVoxel::Wire, false);
+        let mut switches = Vec::new();
+
+        // An SR latch with its set and reset switches.
+        let latch = IVec3::new(20, 0, 0);
+        circuit.insert(latch, TypeVoxel::SrLatch, false);
+        output(&mut circuit, latch);
+        input(&mut circuit, latch + LEFT, IVec3::X, false);
+        input(&mut circuit, latch + RIGHT, -IVec3::X, false);
+        switches.extend([latch + LEFT, latch + RIGHT]);
+
+        // Switch -> Merger -> Bus -> Splitter -> wire.
+        let bus = IVec3::new(40, 0, 0);
+        circuit.insert(bus + IVec3::new(0, 1, -3), TypeVoxel::Switch, false);
+        circuit.insert(bus + IVec3::new(0, 0, -3), TypeVoxel::Out, false);
+        circuit.insert(bus + IVec3::new(0, 0, -2), TypeVoxel::Wire, false);
+        circuit.insert(bus + IVec3::new(0, 0, -1), TypeVoxel::Merger, false);
+        circuit.set_facing(bus + IVec3::new(0, 0, -1), Facing::South);
+        circuit.insert(bus, TypeVoxel::Bus, false);
+        circuit.insert(bus + IVec3::new(0, 0, 1), TypeVoxel::Splitter, false);
+        circuit.set_facing(bus + IVec3::new(0, 0, 1), Facing::South);
+        circuit.insert(bus + IVec3::new(0, 0, 2), TypeVoxel::Wire, false);
+        switches.push(bus + IVec3::new(0, 1, -3));
+
+        // A TriState with its data behind and enable on top.
+        let buffer = IVec3::new(60, 0, 0);
+        input(&mut circuit, buffer + LEFT, IVec3::X, false);
+        input(&mut circuit, buffer + CLOCK, -IVec3::Y, true);
+        circuit.insert(buffer, TypeVoxel::TriState, false);
+        circuit.insert(buffer + IVec3::X, TypeVoxel::Wire, false);
+        switches.extend([buffer + LEFT, buffer + CLOCK]);
+
+        assert_matches_full_sweep(&mut circuit, &switches, 100);
+    }
+
+    #[test]
+    fn zero_period_clocks_from_a_save_are_clamped() {
+        let settings: VoxelSettings = serde_json::from_str(r#"{"Clock":{"period":0,"phase":5,"duty":3}}"#).unwrap();
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, TypeVoxel::Clock, false);
+        circuit.set_settings(IVec3::ZERO, settings);
+        assert_eq!(circuit.settings(IVec3::ZERO), Some(VoxelSettings::Clock { period: 1, phase: 0, duty: 1 }));
+        for _ in 0..4 {
+            circuit.step();
+        }
+    }
+
+    #[test]
+    fn edits_elsewhere_leave_bus_fed_flip_flops_alone() {
+        let mut circuit = Circuit::new();
+        // Switch -> Merger -> Bus -> Splitter -> a wire clocking and feeding a TFlipFlop.
+        circuit.insert(IVec3::new(0, 1, -3), TypeVoxel::Switch, false);
+        circuit.insert(IVec3::new(0, 0, -3), TypeVoxel::Out, false);
+        circuit.insert(IVec3::new(0, 0, -2), TypeVoxel::Wire, false);
+        circuit.insert(IVec3::new(0, 0, -1), TypeVoxel::Merger, false);
+        circuit.set_facing(IVec3::new(0, 0, -1), Facing::South);
+        circuit.insert(IVec3::ZERO, TypeVoxel::Bus, false);
+        circuit.insert(IVec3::new(0, 0, 1), TypeVoxel::Splitter, false);
+        circuit.set_facing(IVec3::new(0, 0, 1), Facing::South);
+        let wire = IVec3::new(0, 0, 2);
+        for position in [wire, IVec3::new(0, 1, 2), IVec3::new(0, 1, 3)] {
+            circuit.insert(position, TypeVoxel::Wire, false);
+        }
+        let flip_flop = IVec3::new(0, 0, 3);
+        circuit.insert(flip_flop, TypeVoxel::TFlipFlop, false);
+        settle(&mut circuit);
+        circuit.set_state(IVec3::new(0, 1, -3), true);
+        settle(&mut circuit);
+        assert_eq!(circuit.state(flip_flop), Some(true));
+
+        circuit.insert(IVec3::new(50, 0, 50), TypeVoxel::Tile, false);
+        for _ in 0..10 {
+            let changes = circuit.step();
+            assert!(changes.iter().all(|change| change.position != wire && change.position != flip_flop));
+        }
+        assert_eq!(circuit.state(flip_flop), Some(true));
+        assert_eq!(circuit.state(wire), Some(true));
+    }
+
+    #[test]
+    fn step_advances_tick() {
+        let mut circuit = Circuit::new();
+        input(&mut circuit, IVec3::ZERO, IVec3::X, true);
+        assert_eq!(circuit.tick(), 0);
+        for _ in 0..3 {
+            circuit.step();
+        }
+        assert_eq!(circuit.tick(), 3);
+    }
+
+    #[test]
+    fn settles_without_a_clock() {
+        let mut circuit = Circuit::new();
+        input(&mut circuit, IVec3::ZERO, IVec3::X, true);
+        assert!(!circuit.is_stable());
+        settle(&mut circuit);
+        assert!(circuit.is_stable());
+
+        circuit.set_state(IVec3::ZERO, false);
+        assert!(!circuit.is_stable());
+        settle(&mut circuit);
+
+        circuit.insert(IVec3::new(0, 0, 5), TypeVoxel::Clock, false);
+        for _ in 0..50 {
+            circuit.step();
+            assert!(!circuit.is_stable());
+        }
+    }
+
+    #[test]
+    fn only_loops_are_flagged_as_oscillating() {
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, TypeVoxel::Clock, false);
+        circuit.set_settings(IVec3::ZERO, VoxelSettings::Clock { period: 2, phase: 0, duty: 1 });
+        circuit.insert(IVec3::X, TypeVoxel::Out, false);
+        circuit.insert(IVec3::X * 2, TypeVoxel::Wire, false);
+        for _ in 0..100 {
+            circuit.step();
+        }
+        assert!(circuit.oscillating_nets(OSCILLATION_TOGGLES).is_empty());
+
+        // A Not gate whose Out feeds back into it.
+        let ring = IVec3::new(0, 0, 10);
+        circuit.insert(ring, TypeVoxel::Not, false);
+        circuit.insert(ring + IVec3::Y, TypeVoxel::Out, false);
+        circuit.insert(ring + IVec3::Y + IVec3::X, TypeVoxel::Wire, false);
+        circuit.insert(ring + IVec3::X, TypeVoxel::Wire, false);
+        for _ in 0..100 {
+            circuit.step();
+        }
+        assert_eq!(circuit.oscillating_nets(OSCILLATION_TOGGLES), vec![vec![ring + IVec3::X, ring + IVec3::Y + IVec3::X]]);
+
+        // An edit elsewhere keeps the ring's streak.
+        circuit.insert(IVec3::new(20, 0, 0), TypeVoxel::Tile, false);
+        circuit.step();
+        assert_eq!(circuit.oscillating_nets(OSCILLATION_TOGGLES).len(), 1);
+
+        // Paused after a change that needs a recompile, the last netlist still answers.
+        circuit.set_contention(ContentionPolicy::WiredOr);
+        assert_eq!(circuit.oscillating_nets(OSCILLATION_TOGGLES).len(), 1);
+        assert_eq!(circuit.combinational_loops().len(), 1);
+        assert!(circuit.level(ring + IVec3::X).is_some());
+    }
+
+    #[test]
+    fn edits_only_touch_the_nets_beside_them() {
+        let mut circuit = Circuit::new();
+        let wire = input(&mut circuit, IVec3::ZERO, IVec3::X, true);
+        let far = input(&mut circuit, IVec3::new(0, 0, 10), IVec3::X, true);
+        settle(&mut circuit);
+        let far_net = circuit.netlist().net_at(far);
+
+        // A wire placed next to a live one joins its net and picks up its value.
+        circuit.insert(wire + IVec3::X, TypeVoxel::Wire, false);
+        circuit.step();
+        assert_eq!(circuit.state(wire + IVec3::X), Some(true));
+        assert_eq!(circuit.netlist().net_at(far), far_net);
+
+        // Removing the Out leaves the wire undriven but still on, and the Switch drives nothing.
+        circuit.remove(IVec3::X);
+        settle(&mut circuit);
+        let netlist = circuit.netlist();
+        assert!(netlist.net_drivers[netlist.net_at(wire).unwrap()].is_empty());
+        assert_eq!(circuit.state(wire + IVec3::X), Some(true));
+        assert_eq!(circuit.netlist().net_at(far), far_net);
+    }
+
+    // The wire an Out above `gate_type` drives, with a Switch lane for each input.
+    fn gate_output(gate_type: TypeVoxel, inputs: &[bool]) -> bool {
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, gate_type, false);
+        let lanes = [IVec3::new(-3, 0, 0), IVec3::new(3, 0, 0)];
+        for (switch, state) in lanes.iter().zip(inputs) {
+            input(&mut circuit, *switch, -switch.signum(), *state);
+        }
+        let result = output(&mut circuit, IVec3::ZERO);
+        settle(&mut circuit);
+        circuit.state(result).unwrap()
+    }
+
+    #[test]
+    fn truth_tables() {
+        let two_inputs: [(TypeVoxel, fn(bool, bool) -> bool); 3] = [
+            (TypeVoxel::Nand, |a, b| !(a && b)),
+            (TypeVoxel::Nor, |a, b| !(a || b)),
+            (TypeVoxel::Xnor, |a, b| a == b),
+        ];
+        for (gate_type, expected) in two_inputs {
+            for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+                assert_eq!(gate_output(gate_type, &[a, b]), expected(a, b), "{:?} {} {}", gate_type, a, b);
+            }
+        }
+        for a in [false, true] {
+            assert_eq!(gate_output(TypeVoxel::Buffer, &[a]), a, "Buffer {}", a);
+        }
+    }
+
+    const LEFT: IVec3 = IVec3::new(-3, 0, 0);
+    const RIGHT: IVec3 = IVec3::new(3, 0, 0);
+    const CLOCK: IVec3 = IVec3::new(0, 3, 0);
+
+    // A storage component at the origin with Switch lanes into -X, +X and the top.
+    fn storage(voxel_type: TypeVoxel) -> Circuit {
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, voxel_type, false);
+        input(&mut circuit, LEFT, IVec3::X, false);
+        input(&mut circuit, RIGHT, -IVec3::X, false);
+        input(&mut circuit, CLOCK, -IVec3::Y, false);
+        settle(&mut circuit);
+        circuit
+    }
+
+    // Flips a switch, lets the circuit settle and returns the component's state.
+    fn switch_to(circuit: &mut Circuit, switch: IVec3, state: bool) -> bool {
+        circuit.set_state(switch, state);
+        settle(circuit);
+        circuit.state(IVec3::ZERO).unwrap()
+    }
+
+    #[test]
+    fn sr_latch_sets_resets_and_holds() {
+        let mut latch = storage(TypeVoxel::SrLatch);
+        assert_eq!(latch.state(IVec3::ZERO), Some(false));
+        assert!(switch_to(&mut latch, LEFT, true));
+        assert!(switch_to(&mut latch, LEFT, false));
+        assert!(!switch_to(&mut latch, RIGHT, true));
+        assert!(!switch_to(&mut latch, RIGHT, false));
+        assert!(switch_to(&mut latch, LEFT, true));
+        assert!(!switch_to(&mut latch, RIGHT, true));
+    }
+
+    #[test]
+    fn jk_flip_flop_toggles_with_both_inputs_on() {
+        let mut flip_flop = storage(TypeVoxel::JkFlipFlop);
+        switch_to(&mut flip_flop, LEFT, true);
+        switch_to(&mut flip_flop, RIGHT, true);
+        assert_eq!(flip_flop.state(IVec3::ZERO), Some(false));
+        assert!(switch_to(&mut flip_flop, CLOCK, true));
+        assert!(switch_to(&mut flip_flop, CLOCK, false));
+        assert!(!switch_to(&mut flip_flop, CLOCK, true));
+        assert!(!switch_to(&mut flip_flop, CLOCK, false));
+        assert!(switch_to(&mut flip_flop, CLOCK, true));
+    }
+
+    #[test]
+    fn t_flip_flop_toggles_on_rising_edges() {
+        let mut flip_flop = storage(TypeVoxel::TFlipFlop);
+        assert!(!switch_to(&mut flip_flop, CLOCK, true));
+        assert!(!switch_to(&mut flip_flop, CLOCK, false));
+        switch_to(&mut flip_flop, LEFT, true);
+        assert!(switch_to(&mut flip_flop, CLOCK, true));
+        assert!(switch_to(&mut flip_flop, CLOCK, false));
+        assert!(!switch_to(&mut flip_flop, CLOCK, true));
+        assert!(!switch_to(&mut flip_flop, CLOCK, false));
+    }
+
+    #[test]
+    fn edge_d_flip_flop_captures_on_the_edge_only() {
+        let mut flip_flop = storage(TypeVoxel::EdgeDFlipFlop);
+        assert!(!switch_to(&mut flip_flop, LEFT, true));
+        assert!(switch_to(&mut flip_flop, CLOCK, true));
+        assert!(switch_to(&mut flip_flop, LEFT, false));
+        assert!(switch_to(&mut flip_flop, CLOCK, false));
+        assert!(!switch_to(&mut flip_flop, CLOCK, true));
+        assert!(!switch_to(&mut flip_flop, LEFT, true));
+    }
+}
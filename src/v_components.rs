@@ -1,5 +1,14 @@
-use bevy::{ecs::component::Component, math::IVec3, reflect::Reflect};
+use bevy::{
+    ecs::component::Component,
+    math::{IVec3, Quat, Vec3},
+    reflect::Reflect,
+};
 use serde::{Deserialize, Serialize};
+use crate::v_config::{
+    ADDER_DEFAULT_WIDTH, BUS_MAX_WIDTH, BUTTON_DEFAULT_HOLD_TICKS, CLOCK_DEFAULT_DUTY, CLOCK_DEFAULT_PERIOD,
+    DELAY_DEFAULT_TICKS, DELAY_MAX_TICKS, MEMORY_DEFAULT_ADDRESS_BITS, MEMORY_DEFAULT_DATA_BITS,
+    MEMORY_MAX_ADDRESS_BITS, PULSE_DEFAULT_TICKS, SCREEN_DEFAULT_SIZE, SCREEN_MAX_SIZE, VOXEL_TYPE_COUNT,
+};
 
 #[derive(Component)]
 pub struct Ground;
@@ -7,7 +16,7 @@ pub struct Ground;
 #[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
 pub struct PositionVoxel(pub IVec3);
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
 pub enum TypeVoxel {
     Tile,
     Wire,
@@ -18,11 +27,356 @@ pub enum TypeVoxel {
     Xor,
     Not,
     DFlipFlop,
+    Clock,
+    Button,
+    Delay,
+    Nand,
+    Nor,
+    Xnor,
+    Buffer,
+    SrLatch,
+    JkFlipFlop,
+    TFlipFlop,
+    EdgeDFlipFlop,
+    Bus,
+    Splitter,
+    Merger,
+    HalfAdder,
+    FullAdder,
+    Adder,
+    Comparator,
+    Mux2,
+    Mux4,
+    Decoder,
+    Rom,
+    Ram,
+    SevenSegment,
+    Screen,
+    Crossover,
+    Via,
+    Transmitter,
+    Receiver,
+    TriState,
+    RisingEdge,
+    FallingEdge,
+    AnyEdge,
+    PulseExtender,
+}
+
+impl TypeVoxel {
+    // Every type, in hotbar order.
+    pub const ALL: [TypeVoxel; VOXEL_TYPE_COUNT] = [
+        TypeVoxel::Tile,
+        TypeVoxel::Wire,
+        TypeVoxel::Out,
+        TypeVoxel::Switch,
+        TypeVoxel::And,
+        TypeVoxel::Or,
+        TypeVoxel::Xor,
+        TypeVoxel::Not,
+        TypeVoxel::DFlipFlop,
+        TypeVoxel::Clock,
+        TypeVoxel::Button,
+        TypeVoxel::Delay,
+        TypeVoxel::Nand,
+        TypeVoxel::Nor,
+        TypeVoxel::Xnor,
+        TypeVoxel::Buffer,
+        TypeVoxel::SrLatch,
+        TypeVoxel::JkFlipFlop,
+        TypeVoxel::TFlipFlop,
+        TypeVoxel::EdgeDFlipFlop,
+        TypeVoxel::Bus,
+        TypeVoxel::Splitter,
+        TypeVoxel::Merger,
+        TypeVoxel::HalfAdder,
+        TypeVoxel::FullAdder,
+        TypeVoxel::Adder,
+        TypeVoxel::Comparator,
+        TypeVoxel::Mux2,
+        TypeVoxel::Mux4,
+        TypeVoxel::Decoder,
+        TypeVoxel::Rom,
+        TypeVoxel::Ram,
+        TypeVoxel::SevenSegment,
+        TypeVoxel::Screen,
+        TypeVoxel::Crossover,
+        TypeVoxel::Via,
+        TypeVoxel::Transmitter,
+        TypeVoxel::Receiver,
+        TypeVoxel::TriState,
+        TypeVoxel::RisingEdge,
+        TypeVoxel::FallingEdge,
+        TypeVoxel::AnyEdge,
+        TypeVoxel::PulseExtender,
+    ];
+
+    // Types evaluated from the nets touching them.
+    pub fn is_gate(&self) -> bool {
+        matches!(
+            self,
+            TypeVoxel::And
+                | TypeVoxel::Or
+                | TypeVoxel::Xor
+                | TypeVoxel::Not
+                | TypeVoxel::Nand
+                | TypeVoxel::Nor
+                | TypeVoxel::Xnor
+                | TypeVoxel::Buffer
+                | TypeVoxel::DFlipFlop
+                | TypeVoxel::SrLatch
+                | TypeVoxel::JkFlipFlop
+                | TypeVoxel::TFlipFlop
+                | TypeVoxel::EdgeDFlipFlop
+                | TypeVoxel::Delay
+                | TypeVoxel::Splitter
+                | TypeVoxel::Merger
+                | TypeVoxel::Transmitter
+                | TypeVoxel::Receiver
+                | TypeVoxel::TriState
+        ) || self.is_macro()
+            || self.is_pulse()
+    }
+
+    // Arithmetic, memory and display components. They compute a whole word from their pins
+    // instead of one bit.
+    pub fn is_macro(&self) -> bool {
+        matches!(
+            self,
+            TypeVoxel::HalfAdder
+                | TypeVoxel::FullAdder
+                | TypeVoxel::Adder
+                | TypeVoxel::Comparator
+                | TypeVoxel::Mux2
+                | TypeVoxel::Mux4
+                | TypeVoxel::Decoder
+                | TypeVoxel::Rom
+                | TypeVoxel::Ram
+                | TypeVoxel::SevenSegment
+                | TypeVoxel::Screen
+        )
+    }
+
+    // Edge detectors and the pulse extender. They compare their input with the previous tick's
+    // and turn on for a fixed number of ticks when it changes.
+    pub fn is_pulse(&self) -> bool {
+        matches!(
+            self,
+            TypeVoxel::RisingEdge | TypeVoxel::FallingEdge | TypeVoxel::AnyEdge | TypeVoxel::PulseExtender
+        )
+    }
+
+    // Types that hold state, so a feedback path through them is not a combinational loop.
+    pub fn is_sequential(&self) -> bool {
+        matches!(
+            self,
+            TypeVoxel::DFlipFlop
+                | TypeVoxel::SrLatch
+                | TypeVoxel::JkFlipFlop
+                | TypeVoxel::TFlipFlop
+                | TypeVoxel::EdgeDFlipFlop
+                | TypeVoxel::Delay
+        ) || self.is_pulse()
+    }
+
+    // Types an adjacent Out reads from. Delays, bus taps, transmitters, tri-state buffers and
+    // arithmetic components drive their net themselves.
+    pub fn is_out_source(&self) -> bool {
+        let drives_net = matches!(
+            self,
+            TypeVoxel::Delay | TypeVoxel::Splitter | TypeVoxel::Merger | TypeVoxel::Transmitter | TypeVoxel::TriState
+        );
+        (self.is_gate() && !self.is_macro() && !drives_net)
+            || matches!(self, TypeVoxel::Switch | TypeVoxel::Clock | TypeVoxel::Button)
+    }
 }
 
 #[derive(Component, Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
 pub struct StateVoxel(pub bool);
 
+// The horizontal direction a gate drives towards. It reads from its back and sides and only
+// an Out in front of it picks up its output. Gates without a facing (older saves) read and
+// drive on every face.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Facing {
+    East,
+    West,
+    South,
+    North,
+}
+
+impl Facing {
+    // The direction the player is looking, so a placed gate drives away from them.
+    pub fn from_look(direction: Vec3) -> Self {
+        if direction.x.abs() >= direction.z.abs() {
+            if direction.x >= 0.0 { Facing::East } else { Facing::West }
+        } else if direction.z >= 0.0 {
+            Facing::South
+        } else {
+            Facing::North
+        }
+    }
+
+    pub fn forward(&self) -> IVec3 {
+        match self {
+            Facing::East => IVec3::new(1, 0, 0),
+            Facing::West => IVec3::new(-1, 0, 0),
+            Facing::South => IVec3::new(0, 0, 1),
+            Facing::North => IVec3::new(0, 0, -1),
+        }
+    }
+
+    // Forward turned a quarter left, seen from above.
+    pub fn left(&self) -> IVec3 {
+        let forward = self.forward();
+        IVec3::new(forward.z, 0, -forward.x)
+    }
+
+    // Meshes are built facing East.
+    pub fn rotation(&self) -> Quat {
+        let angle = match self {
+            Facing::East => 0.0,
+            Facing::North => 90.0,
+            Facing::West => 180.0,
+            Facing::South => -90.0,
+        };
+        Quat::from_rotation_y(f32::to_radians(angle))
+    }
+}
+
+// Wires only join neighbours of the same colour. Every colour connects to components.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum WireColor {
+    #[default]
+    Plain,
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    Cyan,
+    Magenta,
+    Black,
+}
+
+impl WireColor {
+    pub const ALL: [WireColor; 8] = [
+        WireColor::Plain,
+        WireColor::Red,
+        WireColor::Green,
+        WireColor::Blue,
+        WireColor::Yellow,
+        WireColor::Cyan,
+        WireColor::Magenta,
+        WireColor::Black,
+    ];
+
+    pub fn next(&self) -> Self {
+        WireColor::ALL[(*self as usize + 1) % WireColor::ALL.len()]
+    }
+}
+
+// Per-voxel parameters for the types that have any, saved alongside the voxel.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VoxelSettings {
+    // On for `duty` ticks out of every `period`, shifted by `phase` ticks.
+    Clock { period: u32, phase: u32, duty: u32 },
+    // On while held when `hold_ticks` is 0, otherwise for `hold_ticks` ticks after a click.
+    Button { hold_ticks: u32 },
+    // Repeats its input this many ticks later.
+    Delay { ticks: u32 },
+    // How many ticks a PulseExtender stays on after its input turns on.
+    Pulse { ticks: u32 },
+    // The bus bit a Splitter reads or a Merger drives.
+    Tap { bit: u32 },
+    // How many bits of each operand an Adder adds.
+    Width { bits: u32 },
+    // Size of a ROM or RAM: 2^address_bits words of data_bits each.
+    Memory { address_bits: u32, data_bits: u32 },
+    // Shows the low 4 bits as a hex digit, or drives segments a-g and the point from bits 0-7.
+    SevenSegment { decode: bool },
+    // A width x height framebuffer, one row per input word.
+    Screen { width: u32, height: u32 },
+    Wire { color: WireColor },
+}
+
+impl VoxelSettings {
+    pub fn default_for(voxel_type: TypeVoxel) -> Option<Self> {
+        match voxel_type {
+            TypeVoxel::Clock => Some(VoxelSettings::Clock {
+                period: CLOCK_DEFAULT_PERIOD,
+                phase: 0,
+                duty: CLOCK_DEFAULT_DUTY,
+            }),
+            TypeVoxel::Button => Some(VoxelSettings::Button { hold_ticks: BUTTON_DEFAULT_HOLD_TICKS }),
+            TypeVoxel::Delay => Some(VoxelSettings::Delay { ticks: DELAY_DEFAULT_TICKS }),
+            TypeVoxel::PulseExtender => Some(VoxelSettings::Pulse { ticks: PULSE_DEFAULT_TICKS }),
+            TypeVoxel::Splitter | TypeVoxel::Merger => Some(VoxelSettings::Tap { bit: 0 }),
+            TypeVoxel::Adder => Some(VoxelSettings::Width { bits: ADDER_DEFAULT_WIDTH }),
+            TypeVoxel::Rom | TypeVoxel::Ram => Some(VoxelSettings::Memory {
+                address_bits: MEMORY_DEFAULT_ADDRESS_BITS,
+                data_bits: MEMORY_DEFAULT_DATA_BITS,
+            }),
+            TypeVoxel::Wire => Some(VoxelSettings::Wire { color: WireColor::Plain }),
+            TypeVoxel::SevenSegment => Some(VoxelSettings::SevenSegment { decode: true }),
+            TypeVoxel::Screen => Some(VoxelSettings::Screen {
+                width: SCREEN_DEFAULT_SIZE,
+                height: SCREEN_DEFAULT_SIZE,
+            }),
+            _ => None,
+        }
+    }
+
+    // Keeps values from a save inside the ranges the controls allow and the simulation can
+    // shift by: a tap is one of the 64 bus bits, and an Adder leaves the top bit for its carry.
+    // A Clock needs a period of at least a tick and a phase inside it, and pulses and screens
+    // cannot be empty.
+    pub fn clamped(self) -> Self {
+        match self {
+            VoxelSettings::Clock { period, phase, duty } => {
+                let period = period.max(1);
+                VoxelSettings::Clock { period, phase: phase.min(period - 1), duty: duty.min(period) }
+            }
+            VoxelSettings::Pulse { ticks } => VoxelSettings::Pulse { ticks: ticks.max(1) },
+            VoxelSettings::Delay { ticks } => VoxelSettings::Delay { ticks: ticks.clamp(1, DELAY_MAX_TICKS) },
+            VoxelSettings::Tap { bit } => VoxelSettings::Tap { bit: bit.min(BUS_MAX_WIDTH - 1) },
+            VoxelSettings::Width { bits } => VoxelSettings::Width { bits: bits.clamp(1, BUS_MAX_WIDTH - 1) },
+            VoxelSettings::Memory { address_bits, data_bits } => VoxelSettings::Memory {
+                address_bits: address_bits.clamp(1, MEMORY_MAX_ADDRESS_BITS),
+                data_bits: data_bits.clamp(1, BUS_MAX_WIDTH),
+            },
+            VoxelSettings::Screen { width, height } => VoxelSettings::Screen {
+                width: width.clamp(1, SCREEN_MAX_SIZE),
+                height: height.clamp(1, SCREEN_MAX_SIZE),
+            },
+            settings => settings,
+        }
+    }
+}
+
+// The value on a bus net, bit 0 first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusValue {
+    pub width: u32,
+    pub bits: u64,
+}
+
+// The level of a wire net: 0, 1, Z when every driver is a disabled TriState, or X when the
+// drivers disagree under the Error contention policy or a TriState is undefined. The
+// StateVoxel of a wire is on only at High; gates read Z and X as off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Logic {
+    Low,
+    High,
+    HighZ,
+    Unknown,
+}
+
+impl Logic {
+    pub fn from_bool(state: bool) -> Self {
+        if state { Logic::High } else { Logic::Low }
+    }
+}
+
 #[derive(Component)]
 pub struct Sun;
 
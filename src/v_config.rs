@@ -13,6 +13,37 @@ pub const PLAYER_INTERACTION_MAX: f32 = 10.0;
 
 // Simulation Settings
 pub const SIMULATION_RATE: u64 = 100;
+pub const DEFAULT_GATE_DELAY: u32 = 1;
+pub const DEFAULT_WIRE_DELAY: f32 = 0.0;
+pub const GATE_DELAY_MAX: u32 = 16;
+pub const WIRE_DELAY_MAX: f32 = 2.0;
+pub const SIMULATION_RUN_TICKS: u64 = 10;
+pub const SIMULATION_STABLE_LIMIT: u64 = 1000;
+pub const SIMULATION_HISTORY_LENGTH: usize = 512;
+// Value changes kept per probe; older ones scroll off the waveform.
+pub const PROBE_TRACE_LENGTH: usize = 4096;
+pub const OSCILLATION_TOGGLES: u32 = 16;
+pub const OSCILLATION_MAX_GAP: u64 = 2;
+pub const CLOCK_DEFAULT_PERIOD: u32 = 10;
+pub const CLOCK_DEFAULT_DUTY: u32 = 5;
+pub const BUTTON_DEFAULT_HOLD_TICKS: u32 = 0;
+pub const DELAY_DEFAULT_TICKS: u32 = 1;
+// Shared by the settings slider and Ctrl+click, which steps up one tick and wraps to 1.
+pub const DELAY_MAX_TICKS: u32 = 200;
+pub const PULSE_DEFAULT_TICKS: u32 = 4;
+// Bits a bus can carry; a net state is a u64.
+pub const BUS_MAX_WIDTH: u32 = 64;
+// The carry of an Adder takes the bit above its sum, so it adds at most 63 bits.
+pub const ADDER_DEFAULT_WIDTH: u32 = 8;
+pub const MEMORY_DEFAULT_ADDRESS_BITS: u32 = 8;
+pub const MEMORY_DEFAULT_DATA_BITS: u32 = 8;
+pub const MEMORY_MAX_ADDRESS_BITS: u32 = 16;
+pub const SCREEN_DEFAULT_SIZE: u32 = 8;
+// A screen row is one bus word, so a screen is at most 64 pixels wide.
+pub const SCREEN_MAX_SIZE: u32 = 64;
+pub const CHANNEL_DEFAULT_NAME: &str = "default";
+pub const DISPLAY_ON_COLOR: [u8; 4] = [255, 64, 32, 255];
+pub const DISPLAY_OFF_COLOR: [u8; 4] = [24, 8, 8, 255];
 
 // World Generation
 pub const WORLD_SIZE: i32 = 256;
@@ -25,7 +56,10 @@ pub const WORLD_PERCIEVED_ROUGHNESS: f32 = 1.0;
 pub const VOXEL_PERCIEVED_ROUGHNESS: f32 = 1.0;
 pub const VOXEL_METALLIC: f32 = 1.0;
 pub const VOXEL_REFLECTANCE: f32 = 0.0;
-pub const VOXEL_ATLAS_SIZE: usize = 18;
+// Glow of a wire nothing is driving, between the off black and the on green.
+pub const FLOATING_EMISSIVE: Color = Color::rgb(0.2, 0.3, 1.2);
+pub const VOXEL_TYPE_COUNT: usize = 43;
+pub const VOXEL_ATLAS_SIZE: usize = VOXEL_TYPE_COUNT * 2;
 
 // Lighting
 pub const SUN_INTENSITY: f32 = 10000.0;
@@ -37,13 +71,28 @@ pub const AMBIENT_INTENSITY: f32 = 500.0;
 pub const AMBIENT_COLOR: Color = Color::BEIGE;
 
 // Hotbar
-pub const HOTBAR_ELEMENT_NUMBER: usize = 9;
+// Slots shown at once; scrolling past the last one moves to the next page of voxel types.
+pub const HOTBAR_ELEMENT_NUMBER: usize = 10;
 pub const HOTBAR_SLOT_SIZE: f32 = 96.0;
 pub const HOTBAR_SPACING: f32 = 5.0;
 pub const HOTBAR_ABOVE_BOTTOM: f32 = 10.0;
 pub const HOTBAR_BORDER_SIZE: f32 = 10.0;
 pub const HOTBAR_BACKGROUND_COLOR: Color = Color::GRAY;
 pub const HOTBAR_BORDER_COLOR: Color = color::Color::ORANGE_RED;
+pub const SWATCH_SIZE: f32 = 32.0;
+pub const SWATCH_SPACING: f32 = 4.0;
+
+// Wire tints, indexed by WireColor.
+pub const WIRE_COLORS: [Color; 8] = [
+    Color::WHITE,
+    Color::rgb(1.0, 0.3, 0.3),
+    Color::rgb(0.3, 1.0, 0.3),
+    Color::rgb(0.35, 0.5, 1.0),
+    Color::rgb(1.0, 0.9, 0.25),
+    Color::rgb(0.3, 1.0, 1.0),
+    Color::rgb(1.0, 0.35, 1.0),
+    Color::rgb(0.35, 0.35, 0.35),
+];
 
 // Hotbar Descriptor
 pub const DESCRIPTOR_FONT_SIZE: f32 = 64.0;
@@ -52,5 +101,10 @@ pub const DESCRIPTOR_BOTTOM: f32 = 8.0;
 pub const DESCRIPTOR_RIGHT: f32 = 50.0;
 pub const DESCRIPTOR_FADE_TIMER: f32 = 1.0;
 
+// Waveform viewer
+pub const WAVEFORM_DEFAULT_TICKS: u64 = 64;
+pub const WAVEFORM_ROW_HEIGHT: f32 = 28.0;
+pub const WAVEFORM_LABEL_WIDTH: f32 = 220.0;
+
 // Main menu
 
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Color32},
+    EguiContexts,
+};
+use crate::{v_config::OSCILLATION_TOGGLES, v_simulation::SimulationController, v_structure::Voxel};
+
+pub fn diagnostics_panel(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut panel_visible: Local<bool>,
+    voxel: Res<Voxel>,
+    mut controller: ResMut<SimulationController>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyI) && !contexts.ctx_mut().wants_keyboard_input() {
+        *panel_visible = !*panel_visible;
+    }
+    if !*panel_visible {
+        return;
+    }
+
+    let loops = voxel.circuit.combinational_loops();
+    let oscillating = voxel.circuit.oscillating_nets(OSCILLATION_TOGGLES);
+    let contended = voxel.circuit.contended_nets();
+
+    egui::Window::new("Diagnostics")
+        .default_width(400.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Contention policy: {:?}", voxel.circuit.contention()));
+            ui.checkbox(&mut controller.check_full_sweep, "Check every tick against a full sweep (slow)");
+            if loops.is_empty() && oscillating.is_empty() && contended.is_empty() {
+                ui.label(egui::RichText::new("No problems found").color(Color32::LIGHT_GREEN));
+                return;
+            }
+
+            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                for positions in loops {
+                    ui.label(
+                        egui::RichText::new(format!("Combinational loop through {} voxels", positions.len()))
+                            .color(Color32::LIGHT_RED),
+                    );
+                    ui.label(format!("  starting at {}", positions[0]));
+                }
+                for positions in oscillating.iter() {
+                    ui.label(
+                        egui::RichText::new(format!("Net flipped {}+ times in a row", OSCILLATION_TOGGLES))
+                            .color(Color32::YELLOW),
+                    );
+                    ui.label(format!("  wire at {}", positions[0]));
+                }
+                for positions in contended.iter() {
+                    ui.label(
+                        egui::RichText::new("Net has drivers that disagree")
+                            .color(Color32::from_rgb(255, 0, 255)),
+                    );
+                    ui.label(format!("  wire at {}", positions[0]));
+                }
+            });
+        });
+}
+
+pub fn draw_diagnostic_gizmos(voxel: Res<Voxel>, mut gizmos: Gizmos) {
+    let loops = voxel.circuit.combinational_loops().iter().flatten().map(|position| (*position, Color::RED));
+    let oscillating = voxel.circuit.oscillating_nets(OSCILLATION_TOGGLES);
+    let oscillating = oscillating.iter().flatten().map(|position| (*position, Color::ORANGE));
+    let contended = voxel.circuit.contended_nets();
+    let contended = contended.iter().flatten().map(|position| (*position, Color::FUCHSIA));
+
+    for (position, color) in loops.chain(oscillating).chain(contended) {
+        gizmos.cuboid(
+            Transform::from_translation(position.as_vec3()).with_scale(Vec3::splat(1.04)),
+            color,
+        );
+    }
+}
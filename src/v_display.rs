@@ -0,0 +1,130 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use crate::{
+    v_components::{PositionVoxel, TypeVoxel, VoxelSettings},
+    v_config::{DISPLAY_OFF_COLOR, DISPLAY_ON_COLOR, SCREEN_MAX_SIZE},
+    v_structure::Voxel,
+};
+
+// The textured quad on the front of a SevenSegment or Screen, redrawn from the circuit.
+#[derive(Component)]
+pub struct DisplayFace {
+    position: IVec3,
+    image: Handle<Image>,
+}
+
+const SEVEN_SEGMENT_WIDTH: u32 = 9;
+const SEVEN_SEGMENT_HEIGHT: u32 = 13;
+
+// Segments a-g for the hex digits 0-F.
+const HEX_SEGMENTS: [u8; 16] = [
+    0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F, 0x77, 0x7C, 0x39, 0x5E, 0x79, 0x71,
+];
+
+// Gives every newly placed display a quad on its front face. Meshes are built facing East,
+// so the quad sits on +X and turns with the voxel.
+pub fn spawn_display_faces(
+    mut commands: Commands,
+    query: Query<(Entity, &PositionVoxel, &TypeVoxel), Added<TypeVoxel>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (entity, position, voxel_type) in query.iter() {
+        if !matches!(voxel_type, TypeVoxel::SevenSegment | TypeVoxel::Screen) {
+            continue;
+        }
+        let image = images.add(display_image(1, 1, &[false]));
+        let face = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Mesh::from(Rectangle::new(0.9, 0.9))),
+                    material: materials.add(StandardMaterial {
+                        base_color_texture: Some(image.clone()),
+                        unlit: true,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.501, 0.0, 0.0)
+                        .with_rotation(Quat::from_rotation_y(f32::to_radians(90.0))),
+                    ..default()
+                },
+                DisplayFace { position: position.0, image },
+            ))
+            .id();
+        commands.entity(entity).add_child(face);
+    }
+}
+
+// Only touches an image when its pixels change, so idle displays are not re-uploaded. Screen
+// rows are drawn top first with bit 0 on the left.
+pub fn update_display_faces(voxel: Res<Voxel>, query: Query<&DisplayFace>, mut images: ResMut<Assets<Image>>) {
+    for face in query.iter() {
+        let (width, height, pixels) = match voxel.circuit.settings(face.position) {
+            Some(VoxelSettings::SevenSegment { decode }) => {
+                let value = voxel.circuit.value(face.position).unwrap_or(0);
+                let segments = if decode { HEX_SEGMENTS[(value & 0xF) as usize] } else { value as u8 };
+                (SEVEN_SEGMENT_WIDTH, SEVEN_SEGMENT_HEIGHT, seven_segment_pixels(segments))
+            }
+            Some(VoxelSettings::Screen { width, height }) => {
+                let (width, height) = (width.clamp(1, SCREEN_MAX_SIZE), height.clamp(1, SCREEN_MAX_SIZE));
+                let rows = voxel.circuit.memory(face.position).unwrap_or_default();
+                let pixels = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (y, x)))
+                    .map(|(y, x)| rows.get(y as usize).map_or(false, |row| row >> x & 1 == 1))
+                    .collect();
+                (width, height, pixels)
+            }
+            _ => continue,
+        };
+
+        let image = display_image(width, height, &pixels);
+        let unchanged = images.get(&face.image).map_or(false, |current| {
+            current.width() == width && current.height() == height && current.data == image.data
+        });
+        if !unchanged {
+            images.insert(face.image.clone(), image);
+        }
+    }
+}
+
+fn display_image(width: u32, height: u32, pixels: &[bool]) -> Image {
+    let data = pixels
+        .iter()
+        .flat_map(|on| if *on { DISPLAY_ON_COLOR } else { DISPLAY_OFF_COLOR })
+        .collect();
+    Image::new(
+        Extent3d { width, height, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+// Segment a is the top bar, going clockwise to f, then g in the middle and bit 7 the point.
+fn seven_segment_pixels(segments: u8) -> Vec<bool> {
+    let lit = |bit: u32| segments >> bit & 1 == 1;
+    let mut pixels = vec![false; (SEVEN_SEGMENT_WIDTH * SEVEN_SEGMENT_HEIGHT) as usize];
+    for y in 0..SEVEN_SEGMENT_HEIGHT {
+        for x in 0..SEVEN_SEGMENT_WIDTH {
+            let bar = (2..=5).contains(&x);
+            let upper = (2..=5).contains(&y);
+            let lower = (7..=10).contains(&y);
+            let on = (lit(0) && bar && y == 1)
+                || (lit(1) && x == 6 && upper)
+                || (lit(2) && x == 6 && lower)
+                || (lit(3) && bar && y == 11)
+                || (lit(4) && x == 1 && lower)
+                || (lit(5) && x == 1 && upper)
+                || (lit(6) && bar && y == 6)
+                || (lit(7) && x == 8 && y == 11);
+            pixels[(y * SEVEN_SEGMENT_WIDTH + x) as usize] = on;
+        }
+    }
+    pixels
+}
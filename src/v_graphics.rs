@@ -1,9 +1,10 @@
 use crate::a_loading::TextureHandles;
-use crate::v_components::{StateVoxel, TypeVoxel};
-use crate::v_config::{VOXEL_METALLIC, VOXEL_PERCIEVED_ROUGHNESS, VOXEL_REFLECTANCE};
+use crate::v_components::{Logic, PositionVoxel, StateVoxel, TypeVoxel, VoxelSettings};
+use crate::v_config::{FLOATING_EMISSIVE, VOXEL_METALLIC, VOXEL_PERCIEVED_ROUGHNESS, VOXEL_REFLECTANCE, WIRE_COLORS};
 use crate::v_graphics_helper::{
     calculate_indices, calculate_normals, calculate_positions, calculate_uv_coordinates,
 };
+use crate::v_structure::Voxel;
 use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::{prelude::*, render::render_resource::PrimitiveTopology};
@@ -66,12 +67,24 @@ impl VoxelAssets {
 
 pub fn update_voxel_emissive(
     time: Res<Time>,
-    mut query: Query<(&StateVoxel, &mut Handle<StandardMaterial>)>,
+    voxel: Res<Voxel>,
+    mut query: Query<(&PositionVoxel, &StateVoxel, &mut Handle<StandardMaterial>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for (state, material_handle) in query.iter_mut() {
+    for (position, state, material_handle) in query.iter_mut() {
         if let Some(material) = materials.get_mut(&*material_handle) {
-            material.emissive = if state.0 {
+            if let Some(VoxelSettings::Wire { color }) = voxel.circuit.settings(position.0) {
+                material.base_color = WIRE_COLORS[color as usize];
+            }
+            // Wires on a net with disagreeing drivers or an undefined level blink red as an "X"
+            // state; floating ("Z") wires glow a dim blue.
+            let level = voxel.circuit.level(position.0);
+            material.emissive = if voxel.circuit.is_contended(position.0) || level == Some(Logic::Unknown) {
+                let t = (time.elapsed_seconds() * 4.0).sin().abs() * 4.0;
+                Color::rgb(t, 0.0, 0.0)
+            } else if level == Some(Logic::HighZ) {
+                FLOATING_EMISSIVE
+            } else if state.0 {
                 let pulse_frequency = 8.0;
                 let t = (time.elapsed_seconds() * pulse_frequency).sin() * 0.5 + 4.5;
                 Color::rgb(t * 0.5, t, t * 0.5)
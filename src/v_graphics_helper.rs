@@ -1,4 +1,4 @@
-use crate::v_config::VOXEL_ATLAS_SIZE;
+use crate::v_config::{VOXEL_ATLAS_SIZE, VOXEL_TYPE_COUNT};
 
 pub fn calculate_uv_coordinates(texture_index: u32) -> Vec<[f32; 2]> {
     let atlas_width = VOXEL_ATLAS_SIZE as f32;
@@ -9,7 +9,7 @@ pub fn calculate_uv_coordinates(texture_index: u32) -> Vec<[f32; 2]> {
     let top = 0.0;
     let bottom = 1.0;
 
-    let alternate_left = (texture_index + 9) as f32 * texture_size;
+    let alternate_left = (texture_index + VOXEL_TYPE_COUNT as u32) as f32 * texture_size;
     let alternate_right = alternate_left + texture_size;
 
     let mut uv_coordinates = Vec::with_capacity(24);
@@ -1,13 +1,15 @@
 use std::f32::consts::PI;
 use bevy::prelude::*;
+use bevy_egui::EguiContexts;
 use crate::{
     a_loading::TextureHandles,
     v_config::{
         DESCRIPTOR_BOTTOM, DESCRIPTOR_COLOR, DESCRIPTOR_FADE_TIMER, DESCRIPTOR_FONT_SIZE,
         DESCRIPTOR_RIGHT, HOTBAR_ABOVE_BOTTOM, HOTBAR_BACKGROUND_COLOR, HOTBAR_BORDER_COLOR,
         HOTBAR_BORDER_SIZE, HOTBAR_ELEMENT_NUMBER, HOTBAR_SLOT_SIZE, HOTBAR_SPACING, SCREEN_HEIGHT,
-        SCREEN_WIDTH,
+        SCREEN_WIDTH, SWATCH_SIZE, SWATCH_SPACING, VOXEL_TYPE_COUNT, WIRE_COLORS,
     },
+    v_components::WireColor,
     v_selector::VoxelSelector,
 };
 
@@ -17,7 +19,7 @@ pub fn hotbar_ui(
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
 ) {
     if let Some(handle_texture) = texture_handles.image_handles.get(3) {
-        let texture_atlas = TextureAtlasLayout::from_grid(Vec2::new(24.0, 24.0), 9, 1, None, None);
+        let texture_atlas = TextureAtlasLayout::from_grid(Vec2::new(24.0, 24.0), VOXEL_TYPE_COUNT, 1, None, None);
         let texture_atlas_handle = texture_atlases.add(texture_atlas);
 
         let slot_size = HOTBAR_SLOT_SIZE;
@@ -37,11 +39,32 @@ pub fn hotbar_ui(
                 texture_atlas_handle.clone(),
             );
         }
+        for (i, wire_color) in WireColor::ALL.into_iter().enumerate() {
+            spawn_wire_color_swatch(&mut commands, i, wire_color, side_space);
+        }
     } else {
         panic!("Texture handle not found");
     }
 }
 
+// The image in a hotbar slot, by slot number on the current page.
+#[derive(Component)]
+pub struct HotbarSlot(pub usize);
+
+pub fn update_hotbar_page(
+    voxel_selector: Res<VoxelSelector>,
+    mut query: Query<(&HotbarSlot, &mut TextureAtlas, &mut Visibility)>,
+) {
+    if !voxel_selector.is_changed() {
+        return;
+    }
+    for (slot, mut texture_atlas, mut visibility) in query.iter_mut() {
+        let index = voxel_selector.page() * HOTBAR_ELEMENT_NUMBER + slot.0;
+        *visibility = if index < VOXEL_TYPE_COUNT { Visibility::Inherited } else { Visibility::Hidden };
+        texture_atlas.index = index.min(VOXEL_TYPE_COUNT - 1);
+    }
+}
+
 fn spawn_hotbar_slot(
     commands: &mut Commands,
     index: u32,
@@ -66,25 +89,75 @@ fn spawn_hotbar_slot(
             ..Default::default()
         })
         .with_children(|parent| {
-            parent.spawn(AtlasImageBundle {
-                style: Style {
-                    min_width: Val::Px(slot_size - HOTBAR_BORDER_SIZE * 2.0),
-                    min_height: Val::Px(slot_size - HOTBAR_BORDER_SIZE * 2.0),
-                    ..Default::default()
-                },
-                texture_atlas: TextureAtlas {
-                    layout: texture_atlas_handle,
-                    index: index as usize,
+            parent.spawn((
+                AtlasImageBundle {
+                    style: Style {
+                        min_width: Val::Px(slot_size - HOTBAR_BORDER_SIZE * 2.0),
+                        min_height: Val::Px(slot_size - HOTBAR_BORDER_SIZE * 2.0),
+                        ..Default::default()
+                    },
+                    texture_atlas: TextureAtlas {
+                        layout: texture_atlas_handle,
+                        index: index as usize,
+                    },
+                    image: UiImage {
+                        texture: handle_texture,
+                        ..Default::default()
+                    },
+                    ..default()
                 },
-                image: UiImage {
-                    texture: handle_texture,
-                    ..Default::default()
-                },
-                ..default()
-            });
+                HotbarSlot(index as usize),
+            ));
         });
 }
 
+// A button above the hotbar picking the colour of placed wires.
+#[derive(Component)]
+pub struct WireColorSwatch(pub WireColor);
+
+fn spawn_wire_color_swatch(commands: &mut Commands, index: usize, wire_color: WireColor, side_space: f32) {
+    commands.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(SWATCH_SIZE),
+                height: Val::Px(SWATCH_SIZE),
+                top: Val::Px(SCREEN_HEIGHT - HOTBAR_SLOT_SIZE - HOTBAR_ABOVE_BOTTOM - SWATCH_SIZE - SWATCH_SPACING),
+                left: Val::Px(index as f32 * (SWATCH_SIZE + SWATCH_SPACING) + side_space),
+                border: UiRect::all(Val::Px(SWATCH_SPACING)),
+                ..Default::default()
+            },
+            border_color: HOTBAR_BACKGROUND_COLOR.into(),
+            background_color: WIRE_COLORS[wire_color as usize].into(),
+            ..Default::default()
+        },
+        WireColorSwatch(wire_color),
+    ));
+}
+
+// G steps through the colours, or click a swatch while the cursor is free.
+pub fn wire_color_picker(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut voxel_selector: ResMut<VoxelSelector>,
+    mut query: Query<(&WireColorSwatch, &Interaction, &mut BorderColor)>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyG) && !contexts.ctx_mut().wants_keyboard_input() {
+        voxel_selector.wire_color = voxel_selector.wire_color.next();
+    }
+    for (swatch, interaction, _) in query.iter() {
+        if *interaction == Interaction::Pressed && voxel_selector.wire_color != swatch.0 {
+            voxel_selector.wire_color = swatch.0;
+        }
+    }
+    if !voxel_selector.is_changed() {
+        return;
+    }
+    for (swatch, _, mut border_color) in query.iter_mut() {
+        let color = if swatch.0 == voxel_selector.wire_color { HOTBAR_BORDER_COLOR } else { HOTBAR_BACKGROUND_COLOR };
+        *border_color = color.into();
+    }
+}
+
 #[derive(Component)]
 pub struct FadingText;
 
@@ -13,7 +13,13 @@ use bevy_egui::{
     egui::{self, Color32},
     EguiContexts,
 };
-use crate::{v_components::MainCamera, AppState};
+use crate::{
+    v_circuit::ContentionPolicy,
+    v_components::{MainCamera, TypeVoxel},
+    v_config::{GATE_DELAY_MAX, WIRE_DELAY_MAX},
+    v_structure::Voxel,
+    AppState,
+};
 
 pub fn in_game_menu(
     mut contexts: EguiContexts,
@@ -23,8 +29,10 @@ pub fn in_game_menu(
     commands: Commands,
     entities: Query<(Entity, Option<&Name>)>,
     camera_query: Query<Entity, With<MainCamera>>,
+    voxel: ResMut<Voxel>,
 ) {
-    toggle_menu_visibility(keyboard_input, &mut menu_visible);
+    let typing = contexts.ctx_mut().wants_keyboard_input();
+    toggle_menu_visibility(keyboard_input, typing, &mut menu_visible);
 
     if *menu_visible {
         show_in_game_menu(
@@ -34,15 +42,18 @@ pub fn in_game_menu(
             camera_query,
             &mut next_state,
             menu_visible,
+            voxel,
         );
     }
 }
 
+// Escape in a text field only leaves the field.
 fn toggle_menu_visibility(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    typing: bool,
     menu_visible: &mut Local<bool>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if keyboard_input.just_pressed(KeyCode::Escape) && !typing {
         **menu_visible = !**menu_visible;
     }
 }
@@ -54,6 +65,7 @@ fn show_in_game_menu(
     camera_query: Query<Entity, With<MainCamera>>,
     next_state: &mut ResMut<NextState<AppState>>,
     mut menu_visible: Local<bool>,
+    mut voxel: ResMut<Voxel>,
 ) {
     egui::SidePanel::left("in_game_menu_panel")
         .resizable(false)
@@ -67,7 +79,21 @@ fn show_in_game_menu(
                 )
             });
             ui.separator();
-            ui.add_space(200.0);
+            ui.label(
+                egui::RichText::new("When several Outs drive one wire")
+                    .color(Color32::WHITE)
+                    .size(20.0),
+            );
+            let mut contention = voxel.circuit.contention();
+            ui.radio_value(&mut contention, ContentionPolicy::LastDriver, "Last driver wins");
+            ui.radio_value(&mut contention, ContentionPolicy::WiredOr, "Wired-OR");
+            ui.radio_value(&mut contention, ContentionPolicy::Error, "Error (wire reads off)");
+            if contention != voxel.circuit.contention() {
+                voxel.circuit.set_contention(contention);
+            }
+            ui.separator();
+            timing_settings(ui, &mut voxel);
+            ui.add_space(150.0);
             ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                 if ui
                     .button(
@@ -83,4 +109,26 @@ fn show_in_game_menu(
                 }
             });
         });
-}
\ No newline at end of file
+}
+// Ticks each component takes to show a change of its inputs, and ticks added per wire voxel.
+// A Delay takes its ticks from its own settings instead.
+fn timing_settings(ui: &mut egui::Ui, voxel: &mut ResMut<Voxel>) {
+    ui.label(egui::RichText::new("Timing").color(Color32::WHITE).size(20.0));
+    let mut timing = voxel.circuit.timing().clone();
+    ui.add(egui::Slider::new(&mut timing.wire_delay, 0.0..=WIRE_DELAY_MAX).text("ticks per wire"));
+    egui::CollapsingHeader::new("Component delays").show(ui, |ui| {
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            let voxel_types = TypeVoxel::ALL
+                .into_iter()
+                .filter(|voxel_type| (voxel_type.is_gate() || *voxel_type == TypeVoxel::Out) && *voxel_type != TypeVoxel::Delay);
+            for voxel_type in voxel_types {
+                let mut delay = timing.gate_delay(voxel_type) as u32;
+                ui.add(egui::Slider::new(&mut delay, 1..=GATE_DELAY_MAX).text(format!("{:?}", voxel_type)));
+                if delay as u64 != timing.gate_delay(voxel_type) {
+                    timing.set_gate_delay(voxel_type, delay);
+                }
+            }
+        });
+    });
+    voxel.circuit.set_timing(timing);
+}
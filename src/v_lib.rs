@@ -3,7 +3,7 @@ use bevy_mod_raycast::immediate::Raycast;
 use bevy_math::{Ray3d, Vec3A};
 use super::v_config::*;
 use crate::{
-    v_components::{PositionVoxel, StateVoxel, TypeVoxel},
+    v_components::{Facing, PositionVoxel, StateVoxel, TypeVoxel},
     v_structure::Voxel,
 };
 
@@ -14,6 +14,7 @@ pub struct VoxelInfo {
     pub voxel_type: Option<TypeVoxel>,
     pub in_range: bool,
     pub is_on: Option<bool>,
+    pub facing: Facing,
 }
 
 impl VoxelInfo {
@@ -24,6 +25,7 @@ impl VoxelInfo {
             in_range: false,
             voxel_type: None,
             is_on: None,
+            facing: Facing::East,
         }
     }
 }
@@ -82,6 +84,9 @@ pub fn update_info(
     get_query: Query<(&PositionVoxel, &TypeVoxel, &StateVoxel)>,
     gizmos: Gizmos,
 ) {
+    if let Ok(camera_transform) = query.get_single() {
+        voxel_info.facing = Facing::from_look(camera_transform.forward().into());
+    }
     match raycasting(raycast, query, gizmos) {
         Ok((position, adjacent_position, is_in_range)) => {
             voxel_info.position = position;
@@ -105,3 +110,15 @@ fn reset_voxel_info(voxel_info: &mut VoxelInfo) {
     voxel_info.is_on = None;
     voxel_info.voxel_type = None;
 }
+
+// Shows which way the targeted gate drives.
+pub fn draw_facing_gizmo(voxel_info: Res<VoxelInfo>, voxel: Res<Voxel>, mut gizmos: Gizmos) {
+    if !voxel_info.in_range {
+        return;
+    }
+    if let Some(facing) = voxel.circuit.facing(voxel_info.position) {
+        let center = voxel_info.position.as_vec3() + Vec3::Y * 0.55;
+        let forward = facing.forward().as_vec3() * 0.4;
+        gizmos.arrow(center - forward, center + forward, Color::WHITE);
+    }
+}
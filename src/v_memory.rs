@@ -0,0 +1,123 @@
+use std::fs;
+use crate::v_config::MEMORY_MAX_ADDRESS_BITS;
+
+// Largest image accepted: every address of the biggest memory with 64-bit words.
+const MEMORY_MAX_BYTES: usize = 8 << MEMORY_MAX_ADDRESS_BITS;
+
+// ROM and RAM images live in a folder named after the world, next to its save file.
+pub fn memory_file_path(world_name: &str, file_name: &str) -> String {
+    format!("assets/Saves/{}/{}", world_name, file_name)
+}
+
+// Reads an image into words of `data_bits` each. `.bin` files are raw little-endian words,
+// text starting with ':' is Intel HEX, and any other text is whitespace-separated hex words.
+pub fn load_memory_file(world_name: &str, file_name: &str, data_bits: u32) -> Result<Vec<u64>, String> {
+    let path = memory_file_path(world_name, file_name);
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if bytes.len() > MEMORY_MAX_BYTES {
+        return Err(format!("{} is larger than any memory ({} bytes)", path, MEMORY_MAX_BYTES));
+    }
+
+    if file_name.to_lowercase().ends_with(".bin") {
+        return Ok(bytes_to_words(&bytes, data_bits));
+    }
+    let text = String::from_utf8(bytes).map_err(|_| format!("{} is not a text file", path))?;
+    if text.trim_start().starts_with(':') {
+        parse_intel_hex(&text).map(|bytes| bytes_to_words(&bytes, data_bits))
+    } else {
+        parse_hex_words(&text)
+    }
+}
+
+// Each word takes as many bytes as it needs, lowest byte first.
+fn bytes_to_words(bytes: &[u8], data_bits: u32) -> Vec<u64> {
+    let word_bytes = data_bits.clamp(1, 64).div_ceil(8) as usize;
+    bytes
+        .chunks(word_bytes)
+        .map(|chunk| chunk.iter().rev().fold(0, |word, byte| word << 8 | *byte as u64))
+        .collect()
+}
+
+// One word per hex number, with an optional 0x prefix. Everything after '#' or "//" on a line
+// is a comment.
+fn parse_hex_words(text: &str) -> Result<Vec<u64>, String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").split("//").next().unwrap_or(""))
+        .flat_map(|line| line.split_whitespace())
+        .map(|word| {
+            let digits = word.trim_start_matches("0x").trim_start_matches("0X");
+            u64::from_str_radix(digits, 16).map_err(|_| format!("Not a hex word: {}", word))
+        })
+        .collect()
+}
+
+// Data (00), end of file (01), extended segment (02) and extended linear (04) address
+// records. Gaps between records read as zero.
+fn parse_intel_hex(text: &str) -> Result<Vec<u8>, String> {
+    let mut image = Vec::new();
+    let mut base = 0usize;
+
+    for (index, line) in text.lines().map(str::trim).enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let line_number = index + 1;
+        let record = line
+            .strip_prefix(':')
+            // Hex digit pairs are sliced by byte, so anything but ASCII is malformed.
+            .filter(|record| record.is_ascii() && record.len() % 2 == 0)
+            .and_then(|record| {
+                (0..record.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&record[i..i + 2], 16).ok())
+                    .collect::<Option<Vec<u8>>>()
+            })
+            .ok_or(format!("Malformed Intel HEX record on line {}", line_number))?;
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err(format!("Wrong Intel HEX record length on line {}", line_number));
+        }
+        if record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(format!("Intel HEX checksum mismatch on line {}", line_number));
+        }
+
+        let address = (record[1] as usize) << 8 | record[2] as usize;
+        let data = &record[4..record.len() - 1];
+        match record[3] {
+            0x00 => {
+                let start = base + address;
+                if start + data.len() > MEMORY_MAX_BYTES {
+                    return Err(format!("Intel HEX address on line {} is past the largest memory", line_number));
+                }
+                if image.len() < start + data.len() {
+                    image.resize(start + data.len(), 0);
+                }
+                image[start..start + data.len()].copy_from_slice(data);
+            }
+            0x01 => break,
+            0x02 if data.len() == 2 => base = ((data[0] as usize) << 8 | data[1] as usize) << 4,
+            0x04 if data.len() == 2 => base = ((data[0] as usize) << 8 | data[1] as usize) << 16,
+            0x03 | 0x05 => (),
+            record_type => {
+                return Err(format!("Unsupported Intel HEX record type {:02X} on line {}", record_type, line_number))
+            }
+        }
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intel_hex_data_and_end_records() {
+        let text = ":0400000001020304F2\n:00000001FF\n";
+        assert_eq!(parse_intel_hex(text), Ok(vec![1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn non_ascii_intel_hex_is_an_error() {
+        assert!(parse_intel_hex(":0é00000001020304F2\n").is_err());
+        assert!(parse_intel_hex(":éé\n").is_err());
+    }
+}
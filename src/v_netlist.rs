@@ -0,0 +1,737 @@
+use bevy::math::IVec3;
+use std::collections::{HashMap, HashSet};
+use crate::v_circuit::get_adjacent_positions;
+use crate::v_components::{Facing, Logic, StateVoxel, TypeVoxel, VoxelSettings, WireColor};
+
+pub type NetId = usize;
+
+// A logic voxel with the nets it reads from. Faces touching the same net are listed once
+// per face, matching how the gate rules count adjacent wires. Components with distinct
+// inputs (set/reset, J/K, adder operands) read them from `pins`, in the order documented in
+// compile_gate and compile_facing_gate. Adders also drive their carry onto `carry`, and
+// edge detectors watch their input through `clock` to see it change.
+#[derive(Clone)]
+pub struct Gate {
+    pub position: IVec3,
+    pub voxel_type: TypeVoxel,
+    pub inputs: Vec<NetId>,
+    pub clock: Option<NetId>,
+    pub pins: Vec<Option<NetId>>,
+    pub drives: Option<NetId>,
+    pub carry: Option<NetId>,
+    pub bit: u32,
+    pub width: u32,
+}
+
+// An Out voxel, the gates and switches it reads from and the nets it drives.
+#[derive(Clone)]
+pub struct OutDriver {
+    pub position: IVec3,
+    pub sources: Vec<IVec3>,
+    pub nets: Vec<NetId>,
+}
+
+// Wires merged into nets (connected components of same-coloured TypeVoxel::Wire, joined
+// through Vias and Crossovers as flood_net describes) with every gate and Out
+// mapped onto them, so a tick never has to walk the voxel grid. The fanout tables let the
+// scheduler find what to re-evaluate when a net or a source voxel changes. Nets are driven
+// by Outs and by gates that drive a face (Delay, Merger, Splitter and the arithmetic
+// components), listed by position. Bus voxels form nets of their own whose value holds one
+// bit per Merger/Splitter tap; a wire net only uses bit 0. A driven net reads its driver's
+// output word shifted right by `driver_offsets`, which is how an adder's carry gets its bit.
+// Each channel name used by a Transmitter or Receiver gets a net with no voxels, on whenever
+// any of its Transmitters is.
+#[derive(Default, Clone)]
+pub struct Netlist {
+    pub nets: Vec<Vec<IVec3>>,
+    pub net_states: Vec<u64>,
+    pub net_levels: Vec<Logic>,
+    pub net_widths: Vec<u32>,
+    pub net_buses: Vec<bool>,
+    pub net_channels: Vec<bool>,
+    pub tap_bits: HashMap<IVec3, u32>,
+    pub driver_offsets: HashMap<(IVec3, NetId), u32>,
+    pub net_of: HashMap<IVec3, NetId>,
+    pub vias: HashSet<IVec3>,
+    pub gates: Vec<Gate>,
+    pub last_clocks: Vec<bool>,
+    pub outs: Vec<OutDriver>,
+    pub clocks: Vec<IVec3>,
+    pub net_fanout: Vec<Vec<usize>>,
+    pub net_drivers: Vec<Vec<IVec3>>,
+    pub driven_nets: HashMap<IVec3, Vec<NetId>>,
+    pub source_fanout: HashMap<IVec3, Vec<usize>>,
+    pub net_toggles: Vec<NetToggles>,
+    pub net_contended: Vec<bool>,
+    pub combinational_loops: Vec<Vec<IVec3>>,
+    pub net_in_loop: Vec<bool>,
+    pub channel_nets: HashMap<String, NetId>,
+    pub gate_index: HashMap<IVec3, usize>,
+    pub out_index: HashMap<IVec3, usize>,
+    pub free_nets: Vec<NetId>,
+}
+
+// What an update changed: nets that were dropped (their ids may have been reused), the nets
+// that replaced them, the components compiled again and the nets whose drivers changed.
+pub struct NetlistUpdate {
+    pub dropped: Vec<NetId>,
+    pub created: Vec<NetId>,
+    pub components: Vec<IVec3>,
+    pub driven: Vec<NetId>,
+}
+
+// Counts how many times in a row a net has flipped shortly after its last flip.
+#[derive(Default, Clone, Copy)]
+pub struct NetToggles {
+    pub last_tick: u64,
+    pub streak: u32,
+}
+
+impl Netlist {
+    pub fn compile(
+        voxels: &HashMap<IVec3, (TypeVoxel, StateVoxel)>,
+        facings: &HashMap<IVec3, Facing>,
+        settings: &HashMap<IVec3, VoxelSettings>,
+        channels: &HashMap<IVec3, String>,
+        values: &HashMap<IVec3, u64>,
+    ) -> Self {
+        let mut positions: Vec<IVec3> = voxels.keys().copied().collect();
+        positions.sort_by_key(|position| (position.x, position.y, position.z));
+
+        let mut netlist = Netlist::default();
+        for position in positions.iter() {
+            if is_net_voxel(voxels[position].0) && !netlist.net_of.contains_key(position) {
+                netlist.flood_net(*position, voxels, settings, values);
+            }
+        }
+        netlist.vias =
+            positions.iter().filter(|position| voxels[*position].0 == TypeVoxel::Via).copied().collect();
+        for position in positions.iter() {
+            let Some(channel) = channels.get(position) else {
+                continue;
+            };
+            let net = netlist.channel_net(channel);
+            let (voxel_type, state) = voxels[position];
+            if voxel_type == TypeVoxel::Transmitter && state.0 {
+                netlist.net_states[net] = 1;
+                netlist.net_levels[net] = Logic::High;
+            }
+        }
+
+        for position in positions.iter() {
+            netlist.compile_component(*position, voxels, facings, settings, channels);
+        }
+        netlist.link(&HashMap::new());
+        netlist
+    }
+
+    // Brings the netlist up to date after the voxels at `edits` were placed, removed or
+    // changed. Only the nets touching an edit are flooded again and only the components next
+    // to one, or wired to a net that changed, are compiled again; every other net keeps its
+    // id, value and toggle count.
+    pub fn update(
+        &mut self,
+        edits: &[IVec3],
+        voxels: &HashMap<IVec3, (TypeVoxel, StateVoxel)>,
+        facings: &HashMap<IVec3, Facing>,
+        settings: &HashMap<IVec3, VoxelSettings>,
+        channels: &HashMap<IVec3, String>,
+        values: &HashMap<IVec3, u64>,
+    ) -> NetlistUpdate {
+        let mut near: HashSet<IVec3> = HashSet::new();
+        for edit in edits {
+            near.insert(*edit);
+            near.extend(reachable_positions(*edit, voxels));
+        }
+        let mut dropped: Vec<NetId> = near.iter().filter_map(|position| self.net_at(*position)).collect();
+        dropped.sort_unstable();
+        dropped.dedup();
+
+        let mut affected = near.clone();
+        let mut driven = Vec::new();
+        for net in dropped.iter() {
+            affected.extend(self.net_fanout[*net].iter().map(|gate| self.gates[*gate].position));
+            affected.extend(self.net_drivers[*net].iter().copied());
+        }
+        for gate in self.gates.iter().filter(|gate| affected.contains(&gate.position)) {
+            driven.extend(gate.drives.iter().chain(gate.carry.iter()));
+        }
+        for out in self.outs.iter().filter(|out| affected.contains(&out.position)) {
+            driven.extend(out.nets.iter());
+        }
+
+        let mut reflood: Vec<IVec3> = edits.to_vec();
+        for net in dropped.iter() {
+            reflood.extend(self.free_net(*net));
+        }
+        for edit in edits {
+            match voxels.get(edit) {
+                Some((TypeVoxel::Via, _)) => self.vias.insert(*edit),
+                _ => self.vias.remove(edit),
+            };
+        }
+        reflood.sort_by_key(|position| (position.x, position.y, position.z));
+        let mut created = Vec::new();
+        for position in reflood {
+            let is_wire = voxels.get(&position).map_or(false, |(voxel_type, _)| is_net_voxel(*voxel_type));
+            if is_wire && !self.net_of.contains_key(&position) {
+                created.push(self.flood_net(position, voxels, settings, values));
+            }
+        }
+
+        let kept_clocks: HashMap<IVec3, bool> = self
+            .gates
+            .iter()
+            .zip(self.last_clocks.iter())
+            .filter(|(gate, _)| !affected.contains(&gate.position))
+            .map(|(gate, last_clock)| (gate.position, *last_clock))
+            .collect();
+        self.gates.retain(|gate| !affected.contains(&gate.position));
+        self.outs.retain(|out| !affected.contains(&out.position));
+        self.clocks.retain(|clock| !affected.contains(clock));
+        let mut components: Vec<IVec3> = affected.into_iter().filter(|position| voxels.contains_key(position)).collect();
+        components.sort_by_key(|position| (position.x, position.y, position.z));
+        for position in components.iter() {
+            self.compile_component(*position, voxels, facings, settings, channels);
+        }
+        self.link(&kept_clocks);
+
+        for position in components.iter() {
+            driven.extend(self.driven_nets.get(position).into_iter().flatten());
+        }
+        driven.sort_unstable();
+        driven.dedup();
+        driven.retain(|net| !dropped.contains(net) || created.contains(net));
+        NetlistUpdate { dropped, created, components, driven }
+    }
+
+    // Adds the gate, Out or Clock at `position` to the netlist; other voxels are skipped.
+    fn compile_component(
+        &mut self,
+        position: IVec3,
+        voxels: &HashMap<IVec3, (TypeVoxel, StateVoxel)>,
+        facings: &HashMap<IVec3, Facing>,
+        settings: &HashMap<IVec3, VoxelSettings>,
+        channels: &HashMap<IVec3, String>,
+    ) {
+        match voxels[&position].0 {
+            TypeVoxel::Out => {
+                let out = self.compile_out(position, voxels, facings);
+                self.outs.push(out);
+            }
+            voxel_type if voxel_type.is_gate() => {
+                let mut gate = match facings.get(&position) {
+                    Some(facing) => self.compile_facing_gate(position, voxel_type, *facing),
+                    // Bus taps and arithmetic components only make sense with a direction.
+                    None if voxel_type.is_macro() || matches!(voxel_type, TypeVoxel::Merger | TypeVoxel::Splitter) => {
+                        self.compile_facing_gate(position, voxel_type, Facing::East)
+                    }
+                    None => self.compile_gate(position, voxel_type),
+                };
+                match settings.get(&position) {
+                    Some(VoxelSettings::Tap { bit }) => gate.bit = *bit,
+                    Some(VoxelSettings::Width { bits }) => gate.width = *bits,
+                    Some(VoxelSettings::Memory { data_bits, .. }) => gate.width = *data_bits,
+                    _ => (),
+                }
+                let channel_net = channels.get(&position).map(|channel| self.channel_net(channel));
+                match voxel_type {
+                    TypeVoxel::Transmitter => gate.drives = channel_net,
+                    TypeVoxel::Receiver => gate.inputs = channel_net.into_iter().collect(),
+                    _ => (),
+                }
+                self.gates.push(gate);
+            }
+            TypeVoxel::Clock => self.clocks.push(position),
+            _ => (),
+        }
+    }
+
+    // Rebuilds the tables derived from the gates and Outs. Gates keep the clock they last saw
+    // from `last_clocks`; the others start from their clock net's value.
+    fn link(&mut self, last_clocks: &HashMap<IVec3, bool>) {
+        self.last_clocks = self
+            .gates
+            .iter()
+            .map(|gate| match last_clocks.get(&gate.position) {
+                Some(last_clock) => *last_clock,
+                None => gate.clock.map_or(false, |net| self.net_states[net] != 0),
+            })
+            .collect();
+        self.gate_index = self.gates.iter().enumerate().map(|(index, gate)| (gate.position, index)).collect();
+        self.out_index = self.outs.iter().enumerate().map(|(index, out)| (out.position, index)).collect();
+        self.net_widths = vec![1; self.nets.len()];
+        self.tap_bits.clear();
+        self.driver_offsets.clear();
+        for gate in self.gates.iter() {
+            let bus = match gate.voxel_type {
+                TypeVoxel::Merger => gate.drives,
+                TypeVoxel::Splitter => gate.inputs.first().copied(),
+                _ => None,
+            };
+            if let Some(bus) = bus {
+                self.net_widths[bus] = self.net_widths[bus].max(gate.bit + 1);
+            }
+            if gate.voxel_type == TypeVoxel::Merger {
+                self.tap_bits.insert(gate.position, gate.bit);
+            }
+            let output_width = match gate.voxel_type {
+                TypeVoxel::HalfAdder | TypeVoxel::FullAdder | TypeVoxel::Adder => gate.width + 1,
+                TypeVoxel::Comparator => 3,
+                TypeVoxel::Decoder => 8,
+                TypeVoxel::Rom | TypeVoxel::Ram => gate.width,
+                _ => 1,
+            };
+            if let Some(bus) = gate.drives.filter(|net| self.net_buses[*net]) {
+                self.net_widths[bus] = self.net_widths[bus].max(output_width);
+            }
+            if let Some(carry) = gate.carry {
+                self.driver_offsets.insert((gate.position, carry), gate.width);
+            }
+        }
+        self.build_fanout();
+        self.find_combinational_loops();
+    }
+
+    pub fn net_at(&self, position: IVec3) -> Option<NetId> {
+        self.net_of.get(&position).copied()
+    }
+
+    pub fn is_bus(&self, net: NetId) -> bool {
+        self.net_buses[net]
+    }
+
+    pub fn is_channel(&self, net: NetId) -> bool {
+        self.net_channels[net]
+    }
+
+    // The net a component at `position` reaches on its `neighbour` face. Vias only connect
+    // up and down, so a component beside one does not touch its net.
+    fn pin_at(&self, position: IVec3, neighbour: IVec3) -> Option<NetId> {
+        if neighbour.y == position.y && self.vias.contains(&neighbour) {
+            return None;
+        }
+        self.net_at(neighbour)
+    }
+
+    fn wire_at(&self, position: IVec3, neighbour: IVec3) -> Option<NetId> {
+        self.pin_at(position, neighbour).filter(|net| !self.net_buses[*net])
+    }
+
+    fn bus_at(&self, position: IVec3, neighbour: IVec3) -> Option<NetId> {
+        self.pin_at(position, neighbour).filter(|net| self.net_buses[*net])
+    }
+
+    fn build_fanout(&mut self) {
+        self.net_fanout = vec![Vec::new(); self.nets.len()];
+        self.net_drivers = vec![Vec::new(); self.nets.len()];
+        self.driven_nets.clear();
+        self.source_fanout.clear();
+
+        for (index, gate) in self.gates.iter().enumerate() {
+            for net in gate.inputs.iter().chain(gate.clock.iter()).chain(gate.pins.iter().flatten()) {
+                if !self.net_fanout[*net].contains(&index) {
+                    self.net_fanout[*net].push(index);
+                }
+            }
+        }
+
+        let gate_outputs: Vec<(IVec3, NetId)> = self
+            .gates
+            .iter()
+            .flat_map(|gate| gate.drives.iter().chain(gate.carry.iter()).map(|net| (gate.position, *net)))
+            .collect();
+        let out_outputs = self
+            .outs
+            .iter()
+            .flat_map(|out| out.nets.iter().map(|net| (out.position, *net)));
+        let mut outputs: Vec<(IVec3, NetId)> = out_outputs.chain(gate_outputs).collect();
+        outputs.sort_by_key(|(position, _)| (position.x, position.y, position.z));
+        for (position, net) in outputs {
+            self.net_drivers[net].push(position);
+            self.driven_nets.entry(position).or_default().push(net);
+        }
+
+        for (index, out) in self.outs.iter().enumerate() {
+            for source in out.sources.iter() {
+                self.source_fanout.entry(*source).or_default().push(index);
+            }
+        }
+    }
+
+    // Feedback paths that do not pass through a latch, flip-flop or Delay, found as the
+    // strongly connected components of the net -> gate -> (Out ->) net graph. Records the
+    // voxels on each loop and marks the nets that lie on one.
+    fn find_combinational_loops(&mut self) {
+        let gate_base = self.nets.len();
+        let out_base = gate_base + self.gates.len();
+        let node_count = out_base + self.outs.len();
+
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+        for (net, fanout) in self.net_fanout.iter().enumerate() {
+            let combinational = fanout
+                .iter()
+                .filter(|gate| !self.gates[**gate].voxel_type.is_sequential());
+            for gate in combinational {
+                edges[net].push(gate_base + gate);
+            }
+        }
+        for (index, gate) in self.gates.iter().enumerate() {
+            for out in self.source_fanout.get(&gate.position).into_iter().flatten() {
+                edges[gate_base + index].push(out_base + out);
+            }
+            edges[gate_base + index].extend(gate.drives);
+            edges[gate_base + index].extend(gate.carry);
+        }
+        for (index, out) in self.outs.iter().enumerate() {
+            edges[out_base + index].extend(out.nets.iter().copied());
+        }
+
+        let components: Vec<Vec<usize>> = strongly_connected_components(&edges)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .collect();
+        self.net_in_loop = vec![false; self.nets.len()];
+        for node in components.iter().flatten().filter(|node| **node < gate_base) {
+            self.net_in_loop[*node] = true;
+        }
+        self.combinational_loops = components
+            .into_iter()
+            .map(|component| {
+                let mut positions: Vec<IVec3> = component
+                    .into_iter()
+                    .flat_map(|node| match node {
+                        node if node < gate_base => self.nets[node].clone(),
+                        node if node < out_base => vec![self.gates[node - gate_base].position],
+                        node => vec![self.outs[node - out_base].position],
+                    })
+                    .collect();
+                positions.sort_by_key(|position| (position.x, position.y, position.z));
+                positions
+            })
+            .collect();
+    }
+
+    // Joins neighbouring wires (or buses) into one net. Wires only join wires of the same
+    // colour and never buses. A Via only joins what is above and below it, with any colour.
+    // A run of Crossovers passes a horizontal signal straight through to the voxel past it
+    // without joining the crossing direction, and belongs to no net itself.
+    fn flood_net(
+        &mut self,
+        start: IVec3,
+        voxels: &HashMap<IVec3, (TypeVoxel, StateVoxel)>,
+        settings: &HashMap<IVec3, VoxelSettings>,
+        values: &HashMap<IVec3, u64>,
+    ) -> NetId {
+        let net = self.free_nets.last().copied().unwrap_or(self.nets.len());
+        let voxel_type = |position: &IVec3| voxels.get(position).map(|(voxel_type, _)| *voxel_type);
+        let wire_color = |position: &IVec3| match settings.get(position) {
+            Some(VoxelSettings::Wire { color }) => *color,
+            _ => WireColor::Plain,
+        };
+        let connects = |from: &IVec3, to: &IVec3, direction: IVec3| match (voxel_type(from), voxel_type(to)) {
+            (Some(TypeVoxel::Wire), Some(TypeVoxel::Wire)) => wire_color(from) == wire_color(to),
+            (Some(TypeVoxel::Via), Some(TypeVoxel::Wire | TypeVoxel::Via))
+            | (Some(TypeVoxel::Wire), Some(TypeVoxel::Via)) => direction.y != 0,
+            (Some(TypeVoxel::Bus), Some(TypeVoxel::Bus)) => true,
+            _ => false,
+        };
+        let mut wires = Vec::new();
+        let mut stack = vec![start];
+        self.net_of.insert(start, net);
+
+        while let Some(position) = stack.pop() {
+            wires.push(position);
+            for adj_pos in get_adjacent_positions(position) {
+                let direction = adj_pos - position;
+                let mut target = adj_pos;
+                while direction.y == 0 && voxel_type(&target) == Some(TypeVoxel::Crossover) {
+                    target += direction;
+                }
+                if connects(&position, &target, direction) && !self.net_of.contains_key(&target) {
+                    self.net_of.insert(target, net);
+                    stack.push(target);
+                }
+            }
+        }
+
+        // A bus picks up the word its voxels last carried, so recompiling does not glitch
+        // whatever reads it. Buses joined by the edit carry the bits of both.
+        let is_bus = voxel_type(&start) == Some(TypeVoxel::Bus);
+        let state = match is_bus {
+            true => wires.iter().filter_map(|wire| values.get(wire)).fold(0, |bus, value| bus | value),
+            false => wires.iter().any(|wire| voxels[wire].1 .0) as u64,
+        };
+        self.allocate_net(wires, state, is_bus, false)
+    }
+
+    // The net of a Transmitter and Receiver channel, added the first time the name is used.
+    fn channel_net(&mut self, channel: &str) -> NetId {
+        if let Some(net) = self.channel_nets.get(channel) {
+            return *net;
+        }
+        let net = self.allocate_net(Vec::new(), 0, false, true);
+        self.channel_nets.insert(channel.to_string(), net);
+        net
+    }
+
+    // Takes the slot of a net dropped by an earlier update if there is one.
+    fn allocate_net(&mut self, wires: Vec<IVec3>, state: u64, is_bus: bool, is_channel: bool) -> NetId {
+        let level = Logic::from_bool(state != 0);
+        let Some(net) = self.free_nets.pop() else {
+            self.nets.push(wires);
+            self.net_states.push(state);
+            self.net_levels.push(level);
+            self.net_buses.push(is_bus);
+            self.net_channels.push(is_channel);
+            self.net_toggles.push(NetToggles::default());
+            self.net_contended.push(false);
+            return self.nets.len() - 1;
+        };
+        self.nets[net] = wires;
+        self.net_states[net] = state;
+        self.net_levels[net] = level;
+        self.net_buses[net] = is_bus;
+        self.net_channels[net] = is_channel;
+        self.net_toggles[net] = NetToggles::default();
+        self.net_contended[net] = false;
+        net
+    }
+
+    // Empties a net so its slot can be reused and returns the voxels that were on it.
+    fn free_net(&mut self, net: NetId) -> Vec<IVec3> {
+        let wires = std::mem::take(&mut self.nets[net]);
+        for wire in wires.iter() {
+            self.net_of.remove(wire);
+        }
+        self.net_states[net] = 0;
+        self.net_levels[net] = Logic::Low;
+        self.net_contended[net] = false;
+        self.free_nets.push(net);
+        wires
+    }
+
+    // Pin faces:
+    //   DFlipFlop, EdgeDFlipFlop, TFlipFlop: data on the four sides, clock on top.
+    //   SrLatch: set on -X, reset on +X.
+    //   JkFlipFlop: J on -X, K on +X, clock on top.
+    //   Delay: input on -X, drives the wire on +X.
+    //   Edge detectors and PulseExtender: input on -X, watched like a clock.
+    //   TriState: data on -X, enable on top, drives the wire on +X.
+    //   Transmitter: every face is an input, drives its channel.
+    //   Receiver: reads its channel.
+    //   Other gates: every face is an input.
+    // Outputs of everything but the Delay and TriState are read by an adjacent Out.
+    fn compile_gate(&self, position: IVec3, voxel_type: TypeVoxel) -> Gate {
+        let side_positions = [
+            position + IVec3::new(1, 0, 0),
+            position + IVec3::new(-1, 0, 0),
+            position + IVec3::new(0, 0, 1),
+            position + IVec3::new(0, 0, -1),
+        ];
+        let top = self.wire_at(position, position + IVec3::new(0, 1, 0));
+        let minus_x = self.wire_at(position, position + IVec3::new(-1, 0, 0));
+        let plus_x = self.wire_at(position, position + IVec3::new(1, 0, 0));
+
+        let (inputs, clock, pins) = match voxel_type {
+            TypeVoxel::DFlipFlop | TypeVoxel::EdgeDFlipFlop | TypeVoxel::TFlipFlop => (
+                side_positions.iter().filter_map(|side_pos| self.wire_at(position, *side_pos)).collect(),
+                top,
+                Vec::new(),
+            ),
+            TypeVoxel::SrLatch => (Vec::new(), None, vec![minus_x, plus_x]),
+            TypeVoxel::JkFlipFlop => (Vec::new(), top, vec![minus_x, plus_x]),
+            TypeVoxel::Delay => (minus_x.into_iter().collect(), None, Vec::new()),
+            TypeVoxel::TriState => (Vec::new(), None, vec![minus_x, top]),
+            _ if voxel_type.is_pulse() => (Vec::new(), minus_x, Vec::new()),
+            _ => (
+                get_adjacent_positions(position)
+                    .iter()
+                    .filter_map(|adj_pos| self.wire_at(position, *adj_pos))
+                    .collect(),
+                None,
+                Vec::new(),
+            ),
+        };
+        let drives = if matches!(voxel_type, TypeVoxel::Delay | TypeVoxel::TriState) { plus_x } else { None };
+
+        Gate { position, voxel_type, inputs, clock, pins, drives, carry: None, bit: 0, width: 1 }
+    }
+
+    // Pin faces of a gate with a facing, relative to the direction it drives:
+    //   Gates, DFlipFlop, EdgeDFlipFlop, TFlipFlop: inputs on the back, left and right,
+    //   flip-flop clocks on top.
+    //   SrLatch: set on the left, reset on the right.
+    //   JkFlipFlop: J on the left, K on the right, clock on top.
+    //   Delay: input on the back, drives the wire in front.
+    //   TriState: data on the back, enable on top, drives the wire in front or leaves it
+    //   floating.
+    //   Edge detectors and PulseExtender: input on the back, watched like a clock.
+    //   Merger: input on the back, left and right, drives its bit of the bus in front.
+    //   Splitter: reads its bit of the bus behind, drives the wire in front.
+    //   Transmitter: inputs on the back, left and right, drives its channel.
+    //   Receiver: reads its channel.
+    //   HalfAdder, FullAdder, Adder: A on the left, B on the right, carry in on the back
+    //   (not the HalfAdder), sum and carry to the net in front, carry alone to the wire on top.
+    //   Comparator: A on the left, B on the right, equal/less/greater as bits 0/1/2 in front.
+    //   Mux2: D0 on the left, D1 on the right, select on the back, the chosen value in front.
+    //   Mux4: data bits 0-3 on the back, select bit 0 on the left and bit 1 on the right.
+    //   Decoder: a 3-bit select on the back, one of 8 bits set in front.
+    //   Rom: address on the back, the stored word in front.
+    //   Ram: address on the back, data in on the left, write enable on the right, clock on
+    //   top, the stored word in front.
+    //   SevenSegment: a word on the back, plus wires on the left, right and top as bits 1-3.
+    //   Screen: row data on the back, row number on the left, clock on top (rows are written
+    //   continuously without one). Displays drive nothing.
+    // The arithmetic components read and drive wires or buses alike.
+    // Only an Out in front of the gate reads its output.
+    fn compile_facing_gate(&self, position: IVec3, voxel_type: TypeVoxel, facing: Facing) -> Gate {
+        let back = self.wire_at(position, position - facing.forward());
+        let left = self.wire_at(position, position + facing.left());
+        let right = self.wire_at(position, position - facing.left());
+        let top = self.wire_at(position, position + IVec3::new(0, 1, 0));
+        let inputs = [back, left, right].into_iter().flatten().collect();
+
+        let (inputs, clock, pins, drives) = match voxel_type {
+            TypeVoxel::DFlipFlop | TypeVoxel::EdgeDFlipFlop | TypeVoxel::TFlipFlop => (inputs, top, Vec::new(), None),
+            TypeVoxel::SrLatch => (Vec::new(), None, vec![left, right], None),
+            TypeVoxel::JkFlipFlop => (Vec::new(), top, vec![left, right], None),
+            TypeVoxel::Delay => (back.into_iter().collect(), None, Vec::new(), self.wire_at(position, position + facing.forward())),
+            TypeVoxel::TriState => (Vec::new(), None, vec![back, top], self.wire_at(position, position + facing.forward())),
+            _ if voxel_type.is_pulse() => (Vec::new(), back, Vec::new(), None),
+            TypeVoxel::Merger => (inputs, None, Vec::new(), self.bus_at(position, position + facing.forward())),
+            TypeVoxel::Splitter => (
+                self.bus_at(position, position - facing.forward()).into_iter().collect(),
+                None,
+                Vec::new(),
+                self.wire_at(position, position + facing.forward()),
+            ),
+            _ if voxel_type.is_macro() => {
+                let net_back = self.pin_at(position, position - facing.forward());
+                let net_left = self.pin_at(position, position + facing.left());
+                let net_right = self.pin_at(position, position - facing.left());
+                let pins = match voxel_type {
+                    TypeVoxel::HalfAdder | TypeVoxel::Comparator => vec![net_left, net_right],
+                    TypeVoxel::Decoder | TypeVoxel::Rom => vec![net_back],
+                    TypeVoxel::Mux4 | TypeVoxel::Ram => vec![net_back, net_left, net_right],
+                    TypeVoxel::SevenSegment => vec![net_back, net_left, net_right, top],
+                    TypeVoxel::Screen => vec![net_back, net_left],
+                    _ => vec![net_left, net_right, net_back],
+                };
+                let clock = if matches!(voxel_type, TypeVoxel::Ram | TypeVoxel::Screen) { top } else { None };
+                let drives = match voxel_type {
+                    TypeVoxel::SevenSegment | TypeVoxel::Screen => None,
+                    _ => self.pin_at(position, position + facing.forward()),
+                };
+                (Vec::new(), clock, pins, drives)
+            }
+            _ => (inputs, None, Vec::new(), None),
+        };
+        let carry = match voxel_type {
+            TypeVoxel::HalfAdder | TypeVoxel::FullAdder | TypeVoxel::Adder => top,
+            _ => None,
+        };
+
+        Gate { position, voxel_type, inputs, clock, pins, drives, carry, bit: 0, width: 1 }
+    }
+
+    fn compile_out(
+        &self,
+        position: IVec3,
+        voxels: &HashMap<IVec3, (TypeVoxel, StateVoxel)>,
+        facings: &HashMap<IVec3, Facing>,
+    ) -> OutDriver {
+        let adjacent_positions = get_adjacent_positions(position);
+        let sources = adjacent_positions
+            .iter()
+            .filter(|adj_pos| {
+                let faces_out = facings.get(adj_pos).map_or(true, |facing| **adj_pos + facing.forward() == position);
+                faces_out && voxels.get(adj_pos).map_or(false, |(type_voxel, _)| type_voxel.is_out_source())
+            })
+            .copied()
+            .collect();
+
+        let mut nets: Vec<NetId> = adjacent_positions.iter().filter_map(|adj_pos| self.wire_at(position, *adj_pos)).collect();
+        nets.sort();
+        nets.dedup();
+
+        OutDriver { position, sources, nets }
+    }
+}
+
+fn is_net_voxel(voxel_type: TypeVoxel) -> bool {
+    matches!(voxel_type, TypeVoxel::Wire | TypeVoxel::Via | TypeVoxel::Bus)
+}
+
+// The positions whose nets or components an edit at `position` can change: its neighbours,
+// and past a run of Crossovers the voxel a horizontal signal is passed on to.
+fn reachable_positions(position: IVec3, voxels: &HashMap<IVec3, (TypeVoxel, StateVoxel)>) -> Vec<IVec3> {
+    get_adjacent_positions(position)
+        .into_iter()
+        .flat_map(|adj_pos| {
+            let direction = adj_pos - position;
+            let mut target = adj_pos;
+            let mut passed = vec![adj_pos];
+            while direction.y == 0 && voxels.get(&target).map_or(false, |(voxel_type, _)| *voxel_type == TypeVoxel::Crossover) {
+                target += direction;
+                passed.push(target);
+            }
+            passed
+        })
+        .collect()
+}
+
+// Kosaraju's algorithm with explicit stacks, so large builds cannot overflow the call stack.
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let node_count = edges.len();
+    let mut visited = vec![false; node_count];
+    let mut finish_order = Vec::with_capacity(node_count);
+
+    for root in 0..node_count {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next_edge)) = stack.pop() {
+            if let Some(next) = edges[node].get(next_edge) {
+                stack.push((node, next_edge + 1));
+                if !visited[*next] {
+                    visited[*next] = true;
+                    stack.push((*next, 0));
+                }
+            } else {
+                finish_order.push(node);
+            }
+        }
+    }
+
+    let mut reverse_edges: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+    for (node, targets) in edges.iter().enumerate() {
+        for target in targets {
+            reverse_edges[*target].push(node);
+        }
+    }
+
+    let mut assigned = vec![false; node_count];
+    let mut components = Vec::new();
+    for root in finish_order.into_iter().rev() {
+        if assigned[root] {
+            continue;
+        }
+        assigned[root] = true;
+        let mut component = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            component.push(node);
+            for next in reverse_edges[node].iter() {
+                if !assigned[*next] {
+                    assigned[*next] = true;
+                    stack.push(*next);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
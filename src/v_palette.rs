@@ -0,0 +1,102 @@
+use bevy::{
+    ecs::system::{Local, Res, ResMut},
+    input::{keyboard::KeyCode, ButtonInput},
+};
+use bevy_egui::{
+    egui::{self, Color32},
+    EguiContexts,
+};
+use crate::{v_components::TypeVoxel, v_selector::VoxelSelector};
+
+// Every component grouped by what it does, so types past the first hotbar page are easy to find.
+const PALETTE_GROUPS: [(&str, &[TypeVoxel]); 7] = [
+    (
+        "Wiring",
+        &[
+            TypeVoxel::Tile,
+            TypeVoxel::Wire,
+            TypeVoxel::Via,
+            TypeVoxel::Crossover,
+            TypeVoxel::Out,
+            TypeVoxel::Bus,
+            TypeVoxel::Splitter,
+            TypeVoxel::Merger,
+            TypeVoxel::Transmitter,
+            TypeVoxel::Receiver,
+        ],
+    ),
+    ("Inputs", &[TypeVoxel::Switch, TypeVoxel::Button, TypeVoxel::Clock, TypeVoxel::Delay]),
+    (
+        "Gates",
+        &[
+            TypeVoxel::And,
+            TypeVoxel::Or,
+            TypeVoxel::Xor,
+            TypeVoxel::Not,
+            TypeVoxel::Nand,
+            TypeVoxel::Nor,
+            TypeVoxel::Xnor,
+            TypeVoxel::Buffer,
+            TypeVoxel::TriState,
+        ],
+    ),
+    (
+        "Storage",
+        &[
+            TypeVoxel::DFlipFlop,
+            TypeVoxel::EdgeDFlipFlop,
+            TypeVoxel::TFlipFlop,
+            TypeVoxel::JkFlipFlop,
+            TypeVoxel::SrLatch,
+            TypeVoxel::Rom,
+            TypeVoxel::Ram,
+        ],
+    ),
+    (
+        "Arithmetic",
+        &[
+            TypeVoxel::HalfAdder,
+            TypeVoxel::FullAdder,
+            TypeVoxel::Adder,
+            TypeVoxel::Comparator,
+            TypeVoxel::Mux2,
+            TypeVoxel::Mux4,
+            TypeVoxel::Decoder,
+        ],
+    ),
+    (
+        "Pulses",
+        &[TypeVoxel::RisingEdge, TypeVoxel::FallingEdge, TypeVoxel::AnyEdge, TypeVoxel::PulseExtender],
+    ),
+    ("Displays", &[TypeVoxel::SevenSegment, TypeVoxel::Screen]),
+];
+
+pub fn component_palette(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut panel_visible: Local<bool>,
+    mut voxel_selector: ResMut<VoxelSelector>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyC) && !contexts.ctx_mut().wants_keyboard_input() {
+        *panel_visible = !*panel_visible;
+    }
+    if !*panel_visible {
+        return;
+    }
+
+    let selected = voxel_selector.current_voxel_type();
+    egui::Window::new("Components")
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for (group, voxel_types) in PALETTE_GROUPS {
+                ui.label(egui::RichText::new(group).color(Color32::WHITE).size(20.0));
+                ui.horizontal_wrapped(|ui| {
+                    for voxel_type in voxel_types {
+                        if ui.selectable_label(*voxel_type == selected, format!("{:?}", voxel_type)).clicked() {
+                            voxel_selector.select(*voxel_type);
+                        }
+                    }
+                });
+            }
+        });
+}
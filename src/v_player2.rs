@@ -1,6 +1,7 @@
 use std::{cmp::Ordering, f32::consts::TAU, time::Duration};
 use bevy::{input::mouse::MouseWheel, prelude::*, render::color, window::CursorGrabMode};
 use bevy_atmosphere::plugin::AtmosphereCamera;
+use bevy_egui::EguiContexts;
 use bevy_rapier3d::prelude::*;
 use bevy_fps_controller::controller::*;
 use crate::{
@@ -10,11 +11,12 @@ use crate::{
         PLAYER_YAW_SPEED,
     },
     v_graphics::VoxelAssets,
-    v_hotbar::FadeTimer,
+    v_hotbar::{FadeTimer, WireColorSwatch},
     v_lib::VoxelInfo,
     v_selector::VoxelSelector,
     v_structure::Voxel,
     v_plugins::SpeedBar,
+    v_voxel_settings::VoxelSettingsPanel,
 };
 const SPAWN_POINT: Vec3 = Vec3::new(0.0, 1.0, 0.0);
 
@@ -126,13 +128,14 @@ pub fn respawn(mut query: Query<(&mut Transform, &mut Velocity)>) {
 }
 
 pub fn manage_cursor(
+    mut contexts: EguiContexts,
     btn: Res<ButtonInput<MouseButton>>,
     key: Res<ButtonInput<KeyCode>>,
     mut window_query: Query<&mut Window>,
     mut controller_query: Query<&mut FpsController>,
     mut wheel: EventReader<MouseWheel>,
     mut voxel_selector: ResMut<VoxelSelector>,
-    mut query: Query<&mut BorderColor>,
+    mut query: Query<&mut BorderColor, Without<WireColorSwatch>>,
     mut countdown_timer: ResMut<FadeTimer>,
 ) {
     for event in wheel.read() {
@@ -144,7 +147,7 @@ pub fn manage_cursor(
         countdown_timer.timer.reset();
 
         for (i, mut border_color) in query.iter_mut().enumerate() {
-            border_color.0 = if i == voxel_selector.current_index {
+            border_color.0 = if i == voxel_selector.slot() {
                 Color::LIME_GREEN.into()
             } else {
                 Color::DARK_GRAY.into()
@@ -153,7 +156,9 @@ pub fn manage_cursor(
     }
 
     let mut window = window_query.single_mut();
-    let grab_mode = match btn.just_pressed(MouseButton::Left) {
+    // Clicking on a panel uses the panel instead of grabbing the cursor.
+    let clicked = btn.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input();
+    let grab_mode = match clicked {
         true => CursorGrabMode::Locked,
         false => match key.just_pressed(KeyCode::Escape) {
             true => CursorGrabMode::None,
@@ -170,6 +175,7 @@ pub fn manage_cursor(
 }
 
 pub fn voxel_interaction_system(
+    mut contexts: EguiContexts,
     time: Res<Time>,
     mouse_input: Res<ButtonInput<MouseButton>>,
     voxel_assets: Res<VoxelAssets>,
@@ -178,26 +184,28 @@ pub fn voxel_interaction_system(
     mut voxel: ResMut<Voxel>,
     voxel_info: Res<VoxelInfo>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    remove_query: Query<(Entity, &PositionVoxel)>,
     state_query: Query<(Entity, &PositionVoxel, &mut StateVoxel)>,
     materials: ResMut<Assets<StandardMaterial>>,
     meshes: ResMut<Assets<Mesh>>,
-    mut place_timer: Local<Timer>,
-    mut remove_timer: Local<Timer>,
+    (mut place_timer, mut remove_timer): (Local<Timer>, Local<Timer>),
     mut speed_bar: ResMut<SpeedBar>,
+    mut settings_panel: ResMut<VoxelSettingsPanel>,
+    mut held_button: Local<Option<IVec3>>,
 ) {
     let place_delay = Duration::from_millis(200);
     let remove_delay = Duration::from_millis(100);
 
-    speed_bar.speed_index = match keyboard_input.just_pressed(KeyCode::BracketRight) {
-        true => speed_bar.speed_index.clamp(1, 4) + 1,
-        false => match keyboard_input.just_pressed(KeyCode::BracketLeft) {
-            true => speed_bar.speed_index.clamp(2, 5) - 1,
-            false => speed_bar.speed_index,
-        },
-    };
+    // Clicks and keys meant for an egui panel stay there.
+    let typing = contexts.ctx_mut().wants_keyboard_input();
+    let over_panel = contexts.ctx_mut().wants_pointer_input();
 
-    if voxel_info.in_range {
+    if keyboard_input.just_pressed(KeyCode::BracketRight) && !typing {
+        speed_bar.speed_index = speed_bar.speed_index.clamp(1, 4) + 1;
+    } else if keyboard_input.just_pressed(KeyCode::BracketLeft) && !typing {
+        speed_bar.speed_index = speed_bar.speed_index.clamp(2, 5) - 1;
+    }
+
+    if voxel_info.in_range && !over_panel {
         if (mouse_input.just_pressed(MouseButton::Left)
             || (mouse_input.pressed(MouseButton::Left) && place_timer.tick(time.delta()).finished()))
             && !keyboard_input.pressed(KeyCode::ControlLeft)
@@ -210,6 +218,7 @@ pub fn voxel_interaction_system(
                 materials,
                 meshes,
                 false,
+                voxel_info.facing,
             );
             place_timer.reset();
             place_timer.set_duration(place_delay);
@@ -221,12 +230,49 @@ pub fn voxel_interaction_system(
             }
         }
 
+        if voxel_info.voxel_type == Some(TypeVoxel::Button)
+            && mouse_input.just_pressed(MouseButton::Left)
+            && keyboard_input.pressed(KeyCode::ControlLeft)
+        {
+            voxel.press_button(&mut commands, voxel_info.position);
+            *held_button = Some(voxel_info.position);
+        }
+
+        if voxel_info.voxel_type == Some(TypeVoxel::Delay)
+            && mouse_input.just_pressed(MouseButton::Left)
+            && keyboard_input.pressed(KeyCode::ControlLeft)
+        {
+            voxel.circuit.cycle_delay(voxel_info.position);
+        }
+
+        if matches!(voxel_info.voxel_type, Some(TypeVoxel::Splitter | TypeVoxel::Merger))
+            && mouse_input.just_pressed(MouseButton::Left)
+            && keyboard_input.pressed(KeyCode::ControlLeft)
+        {
+            voxel.circuit.cycle_tap(voxel_info.position);
+        }
+
+        let has_channel = voxel.circuit.channel(voxel_info.position).is_some();
+        let opens_channel =
+            has_channel && mouse_input.just_pressed(MouseButton::Left) && keyboard_input.pressed(KeyCode::ControlLeft);
+        let has_settings = voxel.circuit.settings(voxel_info.position).is_some() || has_channel;
+        if opens_channel || (mouse_input.just_pressed(MouseButton::Middle) && has_settings) {
+            settings_panel.position = Some(voxel_info.position);
+        }
+
         if mouse_input.just_pressed(MouseButton::Right)
             || (mouse_input.pressed(MouseButton::Right) && remove_timer.tick(time.delta()).finished())
         {
-            voxel.remove(&mut commands, voxel_info.position, remove_query);
+            voxel.remove(&mut commands, voxel_info.position);
             remove_timer.reset();
             remove_timer.set_duration(remove_delay);
         }
     }
+
+    // Released wherever the player is looking by then.
+    if mouse_input.just_released(MouseButton::Left) {
+        if let Some(position) = held_button.take() {
+            voxel.release_button(&mut commands, position);
+        }
+    }
 }
\ No newline at end of file
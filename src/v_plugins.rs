@@ -1,8 +1,9 @@
 use std::time::Duration;
 use bevy::{
     app::{App, Plugin, Update},
-    asset::Assets,
+    asset::{AssetServer, Assets},
     ecs::{
+        change_detection::DetectChanges,
         component::Component,
         event::EventReader,
         query::With,
@@ -12,11 +13,13 @@ use bevy::{
     hierarchy::BuildChildren,
     prelude::default,
     render::view::Visibility,
+    render::color::Color,
     sprite::{TextureAtlas, TextureAtlasLayout},
+    text::{Text, TextStyle},
     time::{Time, Timer, TimerMode},
     ui::{
-        node_bundles::{AtlasImageBundle, ImageBundle, NodeBundle},
-        AlignItems, FlexDirection, JustifyContent, Style, UiImage, Val,
+        node_bundles::{AtlasImageBundle, ImageBundle, NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiImage, Val,
     },
 };
 use bevy_egui::egui::epaint::image;
@@ -24,8 +27,10 @@ use bevy_math::Vec2;
 
 use crate::{
     a_loading::{SaveNotificationTimer, TextureHandles},
+    v_config::SIMULATION_RUN_TICKS,
     v_save::SaveEvent,
-    v_simulation::MyTimer,
+    v_simulation::{MyTimer, SimulationController, SimulationMode},
+    v_structure::Voxel,
     AppState,
 };
 
@@ -37,11 +42,13 @@ impl Plugin for WidgetPlugin {
         .add_systems(OnEnter(AppState::InGame), (
             setup_save_notification,
             setup_speed_widget, 
+            setup_simulation_status,
         ))
         .add_systems(
             Update, (
                 update_save_notification,
                 update_speed_widget,
+                update_simulation_status,
             ).run_if(in_state(AppState::InGame)),
            
         ); 
@@ -102,16 +109,19 @@ impl SpeedBar {
 pub struct SpeedWidget;
 
 pub fn update_speed_widget(
-    speed_bar: ResMut<SpeedBar>,
+    speed_bar: Res<SpeedBar>,
     mut simulation_timer: ResMut<MyTimer>,
     mut query: Query<&mut TextureAtlas, With<SpeedWidget>>,
 ) {
+    if !speed_bar.is_changed() {
+        return;
+    }
     let index = speed_bar.speed_index - 1;
 
+    // Only sets how often a tick runs; pausing is up to the SimulationController.
     let simulation_speed: u32;
     match index {
-        0 => simulation_speed = 500000,
-        1 => simulation_speed = 500,
+        0 | 1 => simulation_speed = 500,
         2 => simulation_speed = 100,
         3 => simulation_speed = 10,
         4 => simulation_speed = 1,
@@ -147,3 +157,49 @@ pub fn setup_speed_widget(
         SpeedWidget,
     ));
 }
+
+
+#[derive(Component)]
+pub struct SimulationStatus;
+
+pub fn setup_simulation_status(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("Fonts/Retro Gaming.ttf"),
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(28.0),
+            left: Val::Px(170.0),
+            ..default()
+        }),
+        SimulationStatus,
+    ));
+}
+
+pub fn update_simulation_status(
+    controller: Res<SimulationController>,
+    voxel: Res<Voxel>,
+    mut query: Query<&mut Text, With<SimulationStatus>>,
+) {
+    let mode = match controller.mode {
+        SimulationMode::Paused => "PAUSED",
+        SimulationMode::Running => "RUNNING",
+    };
+    let stable = if voxel.circuit.is_stable() { "stable" } else { "settling" };
+
+    for mut text in &mut query {
+        text.sections[0].value = format!(
+            "{}  tick {}  {}\n[P] pause  [.] step  [,] run {}  [U] until stable",
+            mode,
+            voxel.circuit.tick(),
+            stable,
+            SIMULATION_RUN_TICKS,
+        );
+    }
+}
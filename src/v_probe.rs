@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use crate::{
+    v_circuit::Circuit,
+    v_components::{BusValue, Logic},
+    v_config::PROBE_TRACE_LENGTH,
+    v_lib::VoxelInfo,
+    v_main_menu::WorldName,
+    v_simulation::MyTimer,
+    v_structure::Voxel,
+};
+
+// What a probe read on a tick: the level of a wire, or the bits of a bus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProbeValue {
+    Level(Logic),
+    Bus(BusValue),
+}
+
+impl ProbeValue {
+    fn read(circuit: &Circuit, position: IVec3) -> Self {
+        match circuit.bus_value(position) {
+            Some(bus) => ProbeValue::Bus(bus),
+            None => ProbeValue::Level(circuit.level(position).unwrap_or(Logic::Low)),
+        }
+    }
+
+    // A bus counts as on while any bit is set.
+    pub fn is_on(&self) -> bool {
+        match self {
+            ProbeValue::Level(level) => *level == Logic::High,
+            ProbeValue::Bus(bus) => bus.bits != 0,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        match self {
+            ProbeValue::Level(level) => vcd_level(*level).to_string(),
+            ProbeValue::Bus(bus) => format!("{:#x}", bus.bits),
+        }
+    }
+}
+
+fn vcd_level(level: Logic) -> char {
+    match level {
+        Logic::Low => '0',
+        Logic::High => '1',
+        Logic::HighZ => 'z',
+        Logic::Unknown => 'x',
+    }
+}
+
+pub struct Probe {
+    pub position: IVec3,
+    pub label: String,
+    trace: VecDeque<(u64, ProbeValue)>,
+}
+
+impl Probe {
+    // The last PROBE_TRACE_LENGTH value changes as (tick, value), oldest first.
+    pub fn trace(&self) -> &VecDeque<(u64, ProbeValue)> {
+        &self.trace
+    }
+
+    // Declared as a vector in VCD once it has recorded a bus, as wide as the widest one.
+    fn bus_width(&self) -> Option<u32> {
+        self.trace
+            .iter()
+            .filter_map(|(_, value)| match value {
+                ProbeValue::Bus(bus) => Some(bus.width),
+                ProbeValue::Level(_) => None,
+            })
+            .max()
+    }
+
+    pub fn state_at(&self, tick: u64) -> Option<ProbeValue> {
+        let index = self.trace.partition_point(|(changed, _)| *changed <= tick);
+        index.checked_sub(1).map(|index| self.trace[index].1)
+    }
+}
+
+// Voxels marked as probes and the value changes recorded for them on every simulation tick.
+#[derive(Resource, Default)]
+pub struct ProbeRecorder {
+    pub probes: Vec<Probe>,
+}
+
+impl ProbeRecorder {
+    pub fn new() -> Self {
+        ProbeRecorder::default()
+    }
+
+    pub fn toggle(&mut self, position: IVec3, circuit: &Circuit) {
+        if let Some(index) = self.probes.iter().position(|probe| probe.position == position) {
+            self.probes.remove(index);
+            return;
+        }
+        let Some((voxel_type, _)) = circuit.get(position) else {
+            return;
+        };
+
+        self.probes.push(Probe {
+            position,
+            label: format!("{:?}_{}_{}_{}", voxel_type, position.x, position.y, position.z),
+            trace: VecDeque::from([(circuit.tick(), ProbeValue::read(circuit, position))]),
+        });
+    }
+
+    pub fn record(&mut self, circuit: &Circuit) {
+        let tick = circuit.tick();
+        for probe in self.probes.iter_mut() {
+            // After a rewind the recorded future no longer happened.
+            while probe.trace.len() > 1 && probe.trace.back().map_or(false, |(changed, _)| *changed >= tick) {
+                probe.trace.pop_back();
+            }
+
+            let value = ProbeValue::read(circuit, probe.position);
+            if probe.trace.back().map(|(_, last)| *last) != Some(value) {
+                if probe.trace.len() == PROBE_TRACE_LENGTH {
+                    probe.trace.pop_front();
+                }
+                probe.trace.push_back((tick, value));
+            }
+        }
+    }
+
+    pub fn to_vcd(&self, world_name: &str, tick_millis: u64) -> String {
+        let mut vcd = String::new();
+        vcd.push_str(&format!("$date {} $end\n", chrono::Local::now().to_rfc2822()));
+        vcd.push_str("$version Logica $end\n");
+        vcd.push_str(&format!("$comment one simulation tick = {} ms $end\n", tick_millis));
+        vcd.push_str("$timescale 1 ms $end\n");
+        vcd.push_str(&format!("$scope module {} $end\n", world_name.replace(' ', "_")));
+        let widths: Vec<Option<u32>> = self.probes.iter().map(|probe| probe.bus_width()).collect();
+        for (index, probe) in self.probes.iter().enumerate() {
+            let width = widths[index].unwrap_or(1);
+            vcd.push_str(&format!("$var wire {} {} {} $end\n", width, vcd_identifier(index), probe.label));
+        }
+        vcd.push_str("$upscope $end\n$enddefinitions $end\n");
+
+        let mut changes: Vec<(u64, usize, ProbeValue)> = self
+            .probes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, probe)| probe.trace.iter().map(move |(tick, value)| (*tick, index, *value)))
+            .collect();
+        changes.sort_by_key(|(tick, index, _)| (*tick, *index));
+
+        let mut current_tick = None;
+        for (tick, index, value) in changes {
+            if current_tick != Some(tick) {
+                vcd.push_str(&format!("#{}\n", tick * tick_millis));
+                current_tick = Some(tick);
+            }
+            // Bus probes are written as binary vectors. VCD extends a short vector with 0, x or
+            // z from its leftmost digit but pads a leading 1 with zeros, so a wire level recorded
+            // on one is repeated across every bit.
+            let identifier = vcd_identifier(index);
+            match (value, widths[index]) {
+                (ProbeValue::Level(level), None) => vcd.push_str(&format!("{}{}\n", vcd_level(level), identifier)),
+                (ProbeValue::Level(level), Some(width)) => {
+                    let digits = vcd_level(level).to_string().repeat(width as usize);
+                    vcd.push_str(&format!("b{} {}\n", digits, identifier))
+                }
+                (ProbeValue::Bus(bus), _) => vcd.push_str(&format!("b{:b} {}\n", bus.bits, identifier)),
+            }
+        }
+        vcd
+    }
+}
+
+// VCD identifiers are short strings of printable ASCII characters.
+fn vcd_identifier(mut index: usize) -> String {
+    let mut identifier = String::new();
+    loop {
+        identifier.push((b'!' + (index % 94) as u8) as char);
+        index /= 94;
+        if index == 0 {
+            return identifier;
+        }
+        index -= 1;
+    }
+}
+
+fn export_vcd(recorder: &ProbeRecorder, world_name: &str, tick_millis: u64) -> io::Result<()> {
+    let file_path = format!("assets/Saves/{}.vcd", world_name);
+    File::create(file_path)?.write_all(recorder.to_vcd(world_name, tick_millis).as_bytes())
+}
+
+pub fn probe_input_system(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    voxel_info: Res<VoxelInfo>,
+    voxel: Res<Voxel>,
+    timer: Res<MyTimer>,
+    world_name: Res<WorldName>,
+    mut recorder: ResMut<ProbeRecorder>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyX) && voxel_info.in_range && voxel_info.voxel_type.is_some() {
+        recorder.toggle(voxel_info.position, &voxel.circuit);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        let tick_millis = timer.0.duration().as_millis().max(1) as u64;
+        if let Err(e) = export_vcd(&recorder, &world_name.0, tick_millis) {
+            eprintln!("Failed to export waveform: {}", e);
+        } else {
+            println!("Waveform exported to {}.vcd", world_name.0);
+        }
+    }
+}
+
+pub fn draw_probe_gizmos(recorder: Res<ProbeRecorder>, mut gizmos: Gizmos) {
+    for probe in recorder.probes.iter() {
+        gizmos.cuboid(
+            Transform::from_translation(probe.position.as_vec3()).with_scale(Vec3::splat(1.06)),
+            Color::YELLOW,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v_components::{Facing, TypeVoxel, VoxelSettings};
+
+    #[test]
+    fn traces_keep_the_latest_changes() {
+        let mut circuit = Circuit::new();
+        circuit.insert(IVec3::ZERO, TypeVoxel::Clock, false);
+        circuit.set_settings(IVec3::ZERO, VoxelSettings::Clock { period: 2, phase: 0, duty: 1 });
+        let mut recorder = ProbeRecorder::new();
+        recorder.toggle(IVec3::ZERO, &circuit);
+        for _ in 0..PROBE_TRACE_LENGTH * 3 {
+            circuit.step();
+            recorder.record(&circuit);
+        }
+
+        let trace = recorder.probes[0].trace();
+        assert_eq!(trace.len(), PROBE_TRACE_LENGTH);
+        assert_eq!(trace.back().map(|(tick, _)| *tick), Some(circuit.tick()));
+        assert_eq!(recorder.probes[0].state_at(0), None);
+    }
+
+    #[test]
+    fn vcd_writes_levels_and_bus_vectors() {
+        let mut circuit = Circuit::new();
+        // A TriState left disabled, floating the wire in front of it.
+        circuit.insert(IVec3::new(0, 0, -1), TypeVoxel::TriState, false);
+        circuit.set_facing(IVec3::new(0, 0, -1), Facing::South);
+        circuit.insert(IVec3::ZERO, TypeVoxel::Wire, false);
+        circuit.insert(IVec3::new(0, 0, -2), TypeVoxel::Wire, false);
+        circuit.insert(IVec3::new(0, 1, -1), TypeVoxel::Wire, false);
+        // A Switch merged onto bit 3 of a bus.
+        let bus = IVec3::new(5, 0, 0);
+        circuit.insert(bus, TypeVoxel::Bus, false);
+        circuit.insert(bus + IVec3::new(0, 0, -1), TypeVoxel::Merger, false);
+        circuit.set_facing(bus + IVec3::new(0, 0, -1), Facing::South);
+        circuit.set_settings(bus + IVec3::new(0, 0, -1), VoxelSettings::Tap { bit: 3 });
+        circuit.insert(bus + IVec3::new(0, 0, -2), TypeVoxel::Wire, false);
+        circuit.insert(bus + IVec3::new(0, 0, -3), TypeVoxel::Out, false);
+        circuit.insert(bus + IVec3::new(0, 1, -3), TypeVoxel::Switch, true);
+
+        let mut recorder = ProbeRecorder::new();
+        recorder.toggle(IVec3::ZERO, &circuit);
+        recorder.toggle(bus, &circuit);
+        for _ in 0..10 {
+            circuit.step();
+            recorder.record(&circuit);
+        }
+
+        // A probe that read a high wire before the spot became a 4-bit bus.
+        recorder.probes.push(Probe {
+            position: IVec3::new(10, 0, 0),
+            label: "Mixed".to_string(),
+            trace: VecDeque::from([
+                (0, ProbeValue::Level(Logic::High)),
+                (1, ProbeValue::Bus(BusValue { width: 4, bits: 2 })),
+            ]),
+        });
+
+        let vcd = recorder.to_vcd("test", 1);
+        assert!(vcd.contains("$var wire 1 ! Wire_0_0_0 $end"));
+        assert!(vcd.contains("$var wire 4 \" Bus_5_0_0 $end"));
+        assert!(vcd.contains("\nz!\n"));
+        assert!(vcd.contains("\nb1000 \"\n"));
+        assert!(vcd.contains("\nb1111 #\n"));
+        assert!(vcd.contains("\nb10 #\n"));
+    }
+}
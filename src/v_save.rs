@@ -5,19 +5,41 @@ use bevy::ecs::system::{Commands, Local, Query, Res, ResMut};
 use bevy::input::keyboard::KeyCode;
 use bevy::input::ButtonInput;
 use bevy::pbr::StandardMaterial;
+use bevy::math::IVec3;
 use bevy::render::mesh::Mesh;
+use bevy_egui::EguiContexts;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufReader, Write};
-use crate::v_components::{PositionVoxel, StateVoxel, TypeVoxel};
+use crate::v_circuit::ContentionPolicy;
+use crate::v_components::{Facing, PositionVoxel, StateVoxel, TypeVoxel, VoxelSettings};
 use crate::v_graphics::VoxelAssets;
 use crate::v_main_menu::{SelectedWorld, WorldName};
+use crate::v_memory::load_memory_file;
 use crate::v_structure::Voxel;
+use crate::v_timing::TimingModel;
 use chrono::prelude::*;
 
 #[derive(Serialize, Deserialize)]
 pub struct SavedWorld {
     pub voxels: Vec<(PositionVoxel, TypeVoxel, StateVoxel)>,
+    #[serde(default)]
+    pub timing: TimingModel,
+    #[serde(default)]
+    pub contention: ContentionPolicy,
+    #[serde(default)]
+    pub settings: Vec<(PositionVoxel, VoxelSettings)>,
+    #[serde(default)]
+    pub facings: Vec<(PositionVoxel, Facing)>,
+    #[serde(default)]
+    pub memory_files: Vec<(PositionVoxel, String)>,
+    #[serde(default)]
+    pub channels: Vec<(PositionVoxel, String)>,
+    // Words on buses and at the outputs of arithmetic components, which a voxel's on/off
+    // state cannot hold.
+    #[serde(default)]
+    pub values: Vec<(PositionVoxel, u64)>,
 }
 
 #[derive(Event)]
@@ -25,11 +47,29 @@ pub struct SaveEvent;
 
 fn save_world(
     query: Query<(Entity, &PositionVoxel, &TypeVoxel, &StateVoxel)>,
+    voxel: &Voxel,
     world_name: &str,
     mut save_event_writer: EventWriter<SaveEvent>,
 ) -> io::Result<()> {
     let world_data: Vec<_> = query.iter().map(|(_, pos, typ, state)| (*pos, *typ, *state)).collect();
-    let saved_world = SavedWorld { voxels: world_data };
+    let saved_world = SavedWorld {
+        voxels: world_data,
+        timing: voxel.circuit.timing().clone(),
+        contention: voxel.circuit.contention(),
+        settings: voxel.circuit.all_settings().map(|(position, settings)| (PositionVoxel(position), settings)).collect(),
+        facings: voxel.circuit.all_facings().map(|(position, facing)| (PositionVoxel(position), facing)).collect(),
+        memory_files: voxel
+            .circuit
+            .all_memory_files()
+            .map(|(position, file_name)| (PositionVoxel(position), file_name.to_string()))
+            .collect(),
+        channels: voxel
+            .circuit
+            .all_channels()
+            .map(|(position, channel)| (PositionVoxel(position), channel.to_string()))
+            .collect(),
+        values: voxel.circuit.all_values().map(|(position, value)| (PositionVoxel(position), value)).collect(),
+    };
     let serialized = serde_json::to_string(&saved_world)?;
 
     let file_path = format!("assets/Saves/{}.json", world_name);
@@ -47,13 +87,15 @@ pub fn load_world(world_name: &str) -> io::Result<SavedWorld> {
 }
 
 pub fn check_for_save_input(
+    mut contexts: EguiContexts,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     query: Query<(Entity, &PositionVoxel, &TypeVoxel, &StateVoxel)>,
+    voxel: Res<Voxel>,
     world_name: Res<WorldName>,
     mut save_event_writer: EventWriter<SaveEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::F5) {
-        if let Err(e) = save_world(query, &world_name.0, save_event_writer) {
+    if keyboard_input.just_pressed(KeyCode::F5) && !contexts.ctx_mut().wants_keyboard_input() {
+        if let Err(e) = save_world(query, &voxel, &world_name.0, save_event_writer) {
             eprintln!("Failed to save world: {}", e);
         } else {
             println!("World saved to {}", world_name.0);
@@ -74,6 +116,11 @@ pub fn world_loader(
         if let Ok(saved_world) = load_world(world_name_str) {
             // Update the WorldName resource with the loaded world name
             world_name.0 = world_name_str.clone();
+            voxel.circuit.set_timing(saved_world.timing);
+            voxel.circuit.set_contention(saved_world.contention);
+
+            let facings: HashMap<IVec3, Facing> =
+                saved_world.facings.into_iter().map(|(position, facing)| (position.0, facing)).collect();
 
             for (voxel_position, voxel_type, voxel_state) in saved_world.voxels {
                 voxel.lean_place(
@@ -81,11 +128,34 @@ pub fn world_loader(
                     voxel_position.0,
                     voxel_type,
                     voxel_state.0,
+                    facings.get(&voxel_position.0).copied(),
                     &voxel_assets,
                     &mut meshes,
                     &mut materials,
                 );
             }
+            for (voxel_position, settings) in saved_world.settings {
+                voxel.circuit.set_settings(voxel_position.0, settings);
+            }
+            for (voxel_position, channel) in saved_world.channels {
+                voxel.circuit.set_channel(voxel_position.0, channel);
+            }
+            for (voxel_position, value) in saved_world.values {
+                voxel.circuit.restore_value(voxel_position.0, value);
+            }
+            // ROM and RAM contents are read again from their files on every load.
+            for (voxel_position, file_name) in saved_world.memory_files {
+                let Some(VoxelSettings::Memory { data_bits, .. }) = voxel.circuit.settings(voxel_position.0) else {
+                    continue;
+                };
+                match load_memory_file(world_name_str, &file_name, data_bits) {
+                    Ok(words) => voxel.circuit.load_memory(voxel_position.0, words),
+                    Err(e) => eprintln!("{}", e),
+                }
+                voxel.circuit.set_memory_file(voxel_position.0, file_name);
+            }
+            // Compiled now, so a world loaded while paused already shows its levels and diagnostics.
+            voxel.circuit.netlist();
         } else {
             eprintln!("Failed to load world: {}", world_name_str);
         }
@@ -94,6 +164,7 @@ pub fn world_loader(
 
 pub fn autosave_system(
     query: Query<(Entity, &PositionVoxel, &TypeVoxel, &StateVoxel)>,
+    voxel: Res<Voxel>,
     world_name: Res<WorldName>,
     mut autosave_triggered: Local<bool>,
     mut save_event_writer: EventWriter<SaveEvent>,
@@ -103,7 +174,7 @@ pub fn autosave_system(
 
     if current_minute % 5 == 0 && current_second == 0 {
         if !*autosave_triggered {
-            if let Err(e) = save_world(query, &world_name.0, save_event_writer) {
+            if let Err(e) = save_world(query, &voxel, &world_name.0, save_event_writer) {
                 println!("Failed to save world: {}", e);
             } else {
                 println!("World saved successfully.");
@@ -1,35 +1,43 @@
 use bevy::ecs::system::Resource;
-use crate::{v_components::TypeVoxel, v_config::HOTBAR_ELEMENT_NUMBER};
+use crate::{
+    v_components::{TypeVoxel, WireColor},
+    v_config::{HOTBAR_ELEMENT_NUMBER, VOXEL_TYPE_COUNT},
+};
 
 #[derive(Resource, Clone, Copy)]
 pub struct VoxelSelector {
     pub current_index: usize,
+    pub wire_color: WireColor,
 }
 
 impl VoxelSelector {
     pub fn new() -> Self {
-        VoxelSelector { current_index: 0 }
+        VoxelSelector { current_index: 0, wire_color: WireColor::Plain }
     }
 
     pub fn next(&mut self) {
-        self.current_index = (self.current_index + 1) % HOTBAR_ELEMENT_NUMBER;
+        self.current_index = (self.current_index + 1) % VOXEL_TYPE_COUNT;
     }
 
     pub fn previous(&mut self) {
-        self.current_index = (self.current_index + HOTBAR_ELEMENT_NUMBER - 1) % HOTBAR_ELEMENT_NUMBER;
+        self.current_index = (self.current_index + VOXEL_TYPE_COUNT - 1) % VOXEL_TYPE_COUNT;
     }
 
-    pub fn current_voxel_type(&self) -> TypeVoxel {
-        match self.current_index {
-            0 => TypeVoxel::Tile,
-            1 => TypeVoxel::Wire,
-            2 => TypeVoxel::Out,
-            3 => TypeVoxel::Switch,
-            4 => TypeVoxel::And,
-            5 => TypeVoxel::Or,
-            6 => TypeVoxel::Xor,
-            7 => TypeVoxel::Not,
-            _ => TypeVoxel::DFlipFlop,
+    pub fn select(&mut self, voxel_type: TypeVoxel) {
+        if let Some(index) = TypeVoxel::ALL.iter().position(|candidate| *candidate == voxel_type) {
+            self.current_index = index;
         }
     }
+
+    pub fn page(&self) -> usize {
+        self.current_index / HOTBAR_ELEMENT_NUMBER
+    }
+
+    pub fn slot(&self) -> usize {
+        self.current_index % HOTBAR_ELEMENT_NUMBER
+    }
+
+    pub fn current_voxel_type(&self) -> TypeVoxel {
+        TypeVoxel::ALL[self.current_index.min(VOXEL_TYPE_COUNT - 1)]
+    }
 }
\ No newline at end of file
@@ -1,163 +1,228 @@
-use bevy::math::IVec3;
 use bevy::prelude::*;
-use std::collections::{HashMap, HashSet};
-use crate::v_components::{PositionVoxel, StateVoxel, TypeVoxel};
+use bevy_egui::EguiContexts;
+use std::collections::VecDeque;
+use crate::v_circuit::{StateChange, TickDelta};
+use crate::v_components::StateVoxel;
+use crate::v_config::{
+    SIMULATION_HISTORY_LENGTH, SIMULATION_RUN_TICKS, SIMULATION_STABLE_LIMIT,
+};
+use crate::v_probe::ProbeRecorder;
+use crate::v_structure::Voxel;
 
 #[derive(Resource)]
 pub struct MyTimer(pub Timer);
 
-pub fn logic_operation_system(
-    time: Res<Time>,
-    mut timer: ResMut<MyTimer>,
-    mut voxel_query: Query<(Entity, &PositionVoxel, &TypeVoxel, &mut StateVoxel)>,
-) {
-    if timer.0.tick(time.delta()).just_finished() {
-        let mut changes = Vec::new();
-        let mut visited = HashSet::new();
-        let voxel_map = create_voxel_map(&voxel_query);
-
-        for (entity, position_voxel, type_voxel, _) in voxel_query.iter() {
-            match type_voxel {
-                TypeVoxel::Out => {
-                    let is_on = process_out_logic(position_voxel.0, &voxel_map);
-                    changes.push((entity, is_on));
-                    dfs_propagate(position_voxel.0, &voxel_query, &mut visited, is_on, &mut changes);
-                }
-                TypeVoxel::And | TypeVoxel::Or | TypeVoxel::Xor | TypeVoxel::Not | TypeVoxel::DFlipFlop => {
-                    let is_on = process_logic_gate(position_voxel.0, *type_voxel, &voxel_map);
-                    changes.push((entity, is_on));
-                }
-                _ => (),
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationMode {
+    Paused,
+    Running,
+}
+
+// Ticks requested by the player are queued here and run on the next frame, whether or not
+// the timer is running. Starts paused; the speed bar only sets how often the timer fires. With
+// `check_full_sweep` on, every tick is also run as a full sweep on a copy of the circuit and
+// any voxel where the two disagree is reported.
+#[derive(Resource)]
+pub struct SimulationController {
+    pub mode: SimulationMode,
+    pub queued_ticks: u64,
+    pub until_stable: bool,
+    pub check_full_sweep: bool,
+}
+
+impl SimulationController {
+    pub fn new() -> Self {
+        SimulationController {
+            mode: SimulationMode::Paused,
+            queued_ticks: 0,
+            until_stable: false,
+            check_full_sweep: false,
         }
-        apply_changes(&mut voxel_query, changes);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.mode == SimulationMode::Paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.mode = match self.mode {
+            SimulationMode::Paused => SimulationMode::Running,
+            SimulationMode::Running => SimulationMode::Paused,
+        };
+    }
+
+    pub fn run_ticks(&mut self, ticks: u64) {
+        self.queued_ticks += ticks;
+    }
+
+    pub fn run_until_stable(&mut self) {
+        self.until_stable = true;
     }
 }
 
-fn create_voxel_map(voxel_query: &Query<(Entity, &PositionVoxel, &TypeVoxel, &mut StateVoxel)>) -> HashMap<IVec3, (Entity, TypeVoxel, StateVoxel)> {
-    voxel_query
-        .iter()
-        .map(|(entity, position_voxel, type_voxel, state_voxel)| (position_voxel.0, (entity, *type_voxel, *state_voxel)))
-        .collect()
+// Bounded record of the state changes made by each tick. Walking the deltas backwards or
+// forwards reconstructs any tick still in the buffer without storing full snapshots. Each
+// change keeps the state it replaced, which is what walking backwards restores. The circuit's
+// delta for each tick does the same for what voxel states alone do not cover: pulses and
+// releases still pending, component outputs and the memory words written.
+#[derive(Resource)]
+pub struct SimulationHistory {
+    deltas: VecDeque<(u64, Vec<StateChange>, TickDelta)>,
+    cursor: Option<u64>,
 }
 
-fn process_logic_gate(
-    position: IVec3,
-    voxel_type: TypeVoxel,
-    voxel_map: &HashMap<IVec3, (Entity, TypeVoxel, StateVoxel)>,
-) -> bool {
-    let adjacent_positions = get_adjacent_positions(position);
-    let (active_inputs, total_inputs) = adjacent_positions.iter().fold((0, 0), |(active, total), adj_pos| {
-        if let Some((_, TypeVoxel::Wire, state_voxel)) = voxel_map.get(adj_pos) {
-            (active + state_voxel.0 as usize, total + 1)
-        } else {
-            (active, total)
+impl SimulationHistory {
+    pub fn new() -> Self {
+        SimulationHistory {
+            deltas: VecDeque::new(),
+            cursor: None,
         }
-    });
+    }
 
-    match voxel_type {
-        TypeVoxel::And => active_inputs == total_inputs && total_inputs > 0,
-        TypeVoxel::Or => active_inputs > 0,
-        TypeVoxel::Xor => active_inputs == 1,
-        TypeVoxel::Not => total_inputs == 1 && active_inputs == 0,
-        TypeVoxel::DFlipFlop => process_d_flip_flop_logic(position, voxel_map),
-        _ => false,
+    pub fn oldest_tick(&self) -> Option<u64> {
+        self.deltas.front().map(|(tick, _, _)| *tick)
     }
-}
 
-fn dfs_propagate(
-    current_position: IVec3,
-    voxel_query: &Query<(Entity, &PositionVoxel, &TypeVoxel, &mut StateVoxel)>,
-    visited: &mut HashSet<IVec3>,
-    new_state: bool,
-    changes: &mut Vec<(Entity, bool)>,
-) {
-    if visited.contains(&current_position) {
-        return;
+    pub fn newest_tick(&self) -> Option<u64> {
+        self.deltas.back().map(|(tick, _, _)| *tick)
+    }
+
+    // The tick being viewed while scrubbed back, or None when at the live end.
+    pub fn cursor(&self) -> Option<u64> {
+        self.cursor
     }
 
-    visited.insert(current_position);
+    pub fn record(&mut self, tick: u64, changes: &[StateChange], delta: &TickDelta) {
+        // Resuming from a past tick discards the ticks that followed it.
+        if let Some(cursor) = self.cursor.take() {
+            self.deltas.retain(|(recorded, _, _)| *recorded <= cursor);
+        }
+        if self.newest_tick().map_or(false, |newest| newest + 1 != tick) {
+            self.deltas.clear();
+        }
+
+        self.deltas.push_back((tick, changes.to_vec(), delta.clone()));
+        while self.deltas.len() > SIMULATION_HISTORY_LENGTH {
+            self.deltas.pop_front();
+        }
+    }
 
-    for adj_pos in get_adjacent_positions(current_position).iter() {
-        for (entity, pos_voxel, type_voxel, _) in voxel_query.iter() {
-            if *pos_voxel == PositionVoxel(*adj_pos) && matches!(type_voxel, TypeVoxel::Wire) {
-                changes.push((entity, new_state));
-                dfs_propagate(*adj_pos, voxel_query, visited, new_state, changes);
+    // Returns the tick actually reached once `target` is clamped to the recorded range, the
+    // writes that take the voxels there from `current`, and the circuit deltas to walk, in
+    // the order they are to be applied.
+    pub fn seek(&mut self, current: u64, target: u64) -> Option<(u64, Vec<(IVec3, bool)>, Vec<&TickDelta>)> {
+        let (oldest, newest) = (self.oldest_tick()?, self.newest_tick()?);
+        let target = target.clamp(oldest, newest);
+        let mut states = Vec::new();
+        let mut deltas = Vec::new();
+
+        if target < current {
+            for (_, changes, delta) in self.deltas.iter().rev().filter(|(tick, _, _)| *tick > target && *tick <= current) {
+                states.extend(changes.iter().rev().map(|change| (change.position, change.previous)));
+                deltas.push(delta);
+            }
+        } else {
+            for (_, changes, delta) in self.deltas.iter().filter(|(tick, _, _)| *tick > current && *tick <= target) {
+                states.extend(changes.iter().map(|change| (change.position, change.state)));
+                deltas.push(delta);
             }
         }
+
+        self.cursor = (target != newest).then_some(target);
+        Some((target, states, deltas))
     }
 }
 
-fn process_out_logic(
-    position: IVec3,
-    voxel_map: &HashMap<IVec3, (Entity, TypeVoxel, StateVoxel)>,
-) -> bool {
-    get_adjacent_positions(position).iter().any(|adj_pos| {
-        voxel_map.get(adj_pos).map_or(false, |(_, type_voxel, state_voxel)| {
-            matches!(type_voxel, TypeVoxel::And | TypeVoxel::Or | TypeVoxel::Xor | TypeVoxel::Not | TypeVoxel::DFlipFlop | TypeVoxel::Switch) && state_voxel.0
-        })
-    })
+pub fn simulation_control_input(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut controller: ResMut<SimulationController>,
+) {
+    // Keys typed into a text field are not meant for the simulation.
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        controller.toggle_pause();
+    }
+    if keyboard_input.just_pressed(KeyCode::Period) {
+        controller.run_ticks(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Comma) {
+        controller.run_ticks(SIMULATION_RUN_TICKS);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyU) {
+        controller.run_until_stable();
+    }
 }
 
-fn apply_changes(
-    voxel_query: &mut Query<(Entity, &PositionVoxel, &TypeVoxel, &mut StateVoxel)>,
-    changes: Vec<(Entity, bool)>,
+pub fn logic_operation_system(
+    time: Res<Time>,
+    mut timer: ResMut<MyTimer>,
+    mut controller: ResMut<SimulationController>,
+    mut history: ResMut<SimulationHistory>,
+    mut recorder: ResMut<ProbeRecorder>,
+    mut voxel: ResMut<Voxel>,
+    mut state_query: Query<&mut StateVoxel>,
 ) {
-    let change_map: HashMap<Entity, bool> = changes.into_iter().collect();
+    let mut ticks = std::mem::take(&mut controller.queued_ticks);
+    if timer.0.tick(time.delta()).just_finished() && !controller.is_paused() {
+        ticks += 1;
+    }
 
-    for (entity, _, _, mut state_voxel) in voxel_query.iter_mut() {
-        if let Some(&new_state) = change_map.get(&entity) {
-            state_voxel.0 = new_state;
-        }
+    let check = controller.check_full_sweep;
+    for _ in 0..ticks {
+        run_tick(&mut voxel, &mut history, &mut recorder, &mut state_query, check);
     }
-}
 
-pub fn get_adjacent_positions(position: IVec3) -> [IVec3; 6] {
-    [
-        position + IVec3::new(1, 0, 0),
-        position + IVec3::new(-1, 0, 0),
-        position + IVec3::new(0, 1, 0),
-        position + IVec3::new(0, -1, 0),
-        position + IVec3::new(0, 0, 1),
-        position + IVec3::new(0, 0, -1),
-    ]
+    if std::mem::take(&mut controller.until_stable) {
+        let mut remaining = SIMULATION_STABLE_LIMIT;
+        while !voxel.circuit.is_stable() && remaining > 0 {
+            run_tick(&mut voxel, &mut history, &mut recorder, &mut state_query, check);
+            remaining -= 1;
+        }
+        if !voxel.circuit.is_stable() {
+            eprintln!("Circuit did not settle within {} ticks", SIMULATION_STABLE_LIMIT);
+        }
+    }
 }
 
-fn process_d_flip_flop_logic(
-    position: IVec3,
-    voxel_map: &HashMap<IVec3, (Entity, TypeVoxel, StateVoxel)>,
-) -> bool {
-    let top_position = position + IVec3::new(0, 1, 0);
-    let side_positions = [
-        position + IVec3::new(1, 0, 0),
-        position + IVec3::new(-1, 0, 0),
-        position + IVec3::new(0, 0, 1),
-        position + IVec3::new(0, 0, -1),
-    ];
-
-    let (signal, data) = side_positions.iter().fold((0, 0), |(signal, data), side_pos| {
-        if let Some((_, TypeVoxel::Wire, state_voxel)) = voxel_map.get(side_pos) {
-            (signal, data + state_voxel.0 as usize)
-        } else {
-            (signal, data)
-        }
+fn run_tick(
+    voxel: &mut Voxel,
+    history: &mut SimulationHistory,
+    recorder: &mut ProbeRecorder,
+    state_query: &mut Query<&mut StateVoxel>,
+    check_full_sweep: bool,
+) {
+    let reference = check_full_sweep.then(|| {
+        let mut reference = voxel.circuit.clone();
+        reference.step_full_sweep();
+        reference
     });
 
-    let signal = voxel_map
-    .get(&top_position)
-    .and_then(|(_, type_voxel, state_voxel)| {
-        if matches!(type_voxel, TypeVoxel::Wire) {
-            Some(state_voxel.0 as usize)
-        } else {
-            None
+    let changes = voxel.circuit.step();
+
+    if let Some(reference) = reference {
+        for position in voxel.circuit.mismatches(&reference) {
+            eprintln!("Event-driven simulation diverged from full sweep at {:?}", position);
         }
-    })
-    .unwrap_or(0);
-    let current_state = voxel_map.get(&position).map_or(false, |(_, _, state)| state.0);
+    }
+    history.record(voxel.circuit.tick(), &changes, voxel.circuit.delta());
+    recorder.record(&voxel.circuit);
+    apply_changes(voxel, state_query, changes.iter().map(|change| (change.position, change.state)).collect());
+}
 
-    match (signal, data) {
-        (s, d) if s > 0 && d > 0 => true,
-        (s, 0) if s > 0 => false,
-        _ => current_state,
+pub fn apply_changes(
+    voxel: &Voxel,
+    state_query: &mut Query<&mut StateVoxel>,
+    changes: Vec<(IVec3, bool)>,
+) {
+    for (position, new_state) in changes {
+        if let Some(mut state_voxel) = voxel
+            .entity(position)
+            .and_then(|entity| state_query.get_mut(entity).ok())
+        {
+            state_voxel.0 = new_state;
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,11 @@
 use crate::{
-    v_components::{PositionVoxel, StateVoxel, TypeVoxel},
+    v_circuit::Circuit,
+    v_components::{Facing, PositionVoxel, StateVoxel, TypeVoxel, VoxelSettings},
     v_graphics::VoxelAssets,
     v_selector::VoxelSelector,
 };
 use bevy::ecs::system::Query;
+use bevy::hierarchy::DespawnRecursiveExt;
 use bevy::ecs::system::Resource;
 use bevy::{
     asset::Assets,
@@ -17,13 +19,21 @@ use bevy::{
     transform::components::Transform,
 };
 use bevy_rapier3d::geometry::Collider;
+use std::collections::HashMap;
 
-#[derive(Resource)]
-pub struct Voxel;
+#[derive(Resource, Default)]
+pub struct Voxel {
+    pub circuit: Circuit,
+    entities: HashMap<IVec3, Entity>,
+}
 
 impl Voxel {
     pub fn new() -> Self {
-        Voxel
+        Voxel { circuit: Circuit::new(), entities: HashMap::new() }
+    }
+
+    pub fn entity(&self, position: IVec3) -> Option<Entity> {
+        self.entities.get(&position).copied()
     }
 
     pub fn get(
@@ -50,6 +60,24 @@ impl Voxel {
         {
             *voxel_state = StateVoxel(new_state);
             commands.entity(entity).insert(StateVoxel(new_state));
+            self.circuit.set_state(position, new_state);
+        }
+    }
+
+    pub fn press_button(&mut self, commands: &mut Commands, position: IVec3) {
+        self.circuit.press_button(position);
+        self.show_state(commands, position);
+    }
+
+    pub fn release_button(&mut self, commands: &mut Commands, position: IVec3) {
+        self.circuit.release_button(position);
+        self.show_state(commands, position);
+    }
+
+    // Copies the circuit's state of a voxel onto its entity right away, without waiting for a tick.
+    fn show_state(&self, commands: &mut Commands, position: IVec3) {
+        if let (Some(entity), Some(state)) = (self.entity(position), self.circuit.state(position)) {
+            commands.entity(entity).insert(StateVoxel(state));
         }
     }
 
@@ -62,24 +90,22 @@ impl Voxel {
         mut materials: ResMut<Assets<StandardMaterial>>,
         mut meshes: ResMut<Assets<Mesh>>,
         state: bool,
+        facing: Facing,
     ) {
         let voxel_type = voxel_selector.current_voxel_type();
-        let voxel_mesh_handle = voxel_assets.create_voxel_mesh(voxel_type, &mut meshes);
-        let atlas_material = voxel_assets.atlas_material(&mut materials);
-
-        commands
-            .spawn((
-                PbrBundle {
-                    mesh: voxel_mesh_handle,
-                    material: atlas_material,
-                    transform: Transform::from_translation(position.as_vec3()),
-                    ..Default::default()
-                },
-                PositionVoxel(position),
-                voxel_type,
-                StateVoxel(state),
-                Collider::cuboid(0.5, 0.5, 0.5),
-            ));
+        self.lean_place(
+            commands,
+            position,
+            voxel_type,
+            state,
+            voxel_type.is_gate().then_some(facing),
+            voxel_assets,
+            &mut meshes,
+            &mut materials,
+        );
+        if voxel_type == TypeVoxel::Wire {
+            self.circuit.set_settings(position, VoxelSettings::Wire { color: voxel_selector.wire_color });
+        }
     }
 
     pub fn lean_place(
@@ -88,18 +114,20 @@ impl Voxel {
         position: IVec3,
         voxel_type: TypeVoxel,
         state: bool,
+        facing: Option<Facing>,
         voxel_assets: &Res<VoxelAssets>,
         mut meshes: &mut ResMut<Assets<Mesh>>,
         materials: &mut ResMut<Assets<StandardMaterial>>,
     ) {
         let voxel_mesh_handle = voxel_assets.create_voxel_mesh(voxel_type, &mut meshes);
         let atlas_material = voxel_assets.atlas_material(materials);
+        let rotation = facing.map_or(Default::default(), |facing| facing.rotation());
 
-        commands.spawn((
+        let mut entity = commands.spawn((
             PbrBundle {
                 mesh: voxel_mesh_handle,
                 material: atlas_material,
-                transform: Transform::from_translation(position.as_vec3()),
+                transform: Transform::from_translation(position.as_vec3()).with_rotation(rotation),
                 ..Default::default()
             },
             PositionVoxel(position),
@@ -107,18 +135,26 @@ impl Voxel {
             StateVoxel(state),
             Collider::cuboid(0.5, 0.5, 0.5),
         ));
+        if let Some(facing) = facing {
+            entity.insert(facing);
+        }
+        let entity = entity.id();
+        self.track(position, entity, voxel_type, state);
+        if let Some(facing) = facing {
+            self.circuit.set_facing(position, facing);
+        }
     }
 
-    pub fn remove(
-        &mut self,
-        commands: &mut Commands,
-        position: IVec3,
-        query: Query<(Entity, &PositionVoxel)>,
-    ) {
-        for (entity, voxel_position) in query.iter() {
-            if voxel_position.0 == position {
-                commands.entity(entity).despawn();
-            }
+    pub fn remove(&mut self, commands: &mut Commands, position: IVec3) {
+        if let Some(entity) = self.entities.remove(&position) {
+            // Displays carry their screen as a child.
+            commands.entity(entity).despawn_recursive();
+            self.circuit.remove(position);
         }
     }
+
+    fn track(&mut self, position: IVec3, entity: Entity, voxel_type: TypeVoxel, state: bool) {
+        self.entities.insert(position, entity);
+        self.circuit.insert(position, voxel_type, state);
+    }
 }
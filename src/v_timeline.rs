@@ -0,0 +1,84 @@
+use bevy::{
+    ecs::system::{Local, Query, Res, ResMut},
+    input::{keyboard::KeyCode, ButtonInput},
+};
+use bevy_egui::{
+    egui::{self, Color32},
+    EguiContexts,
+};
+use crate::{
+    v_components::StateVoxel,
+    v_simulation::{apply_changes, SimulationController, SimulationHistory, SimulationMode},
+    v_structure::Voxel,
+};
+
+pub fn timeline_panel(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut panel_visible: Local<bool>,
+    mut history: ResMut<SimulationHistory>,
+    mut controller: ResMut<SimulationController>,
+    mut voxel: ResMut<Voxel>,
+    mut state_query: Query<&mut StateVoxel>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyT) && !contexts.ctx_mut().wants_keyboard_input() {
+        *panel_visible = !*panel_visible;
+    }
+    let (Some(oldest), Some(newest)) = (history.oldest_tick(), history.newest_tick()) else {
+        return;
+    };
+    if !*panel_visible {
+        return;
+    }
+
+    let current = voxel.circuit.tick();
+    let mut target = current;
+    let mut resume = false;
+
+    egui::Window::new("Timeline")
+        .resizable(false)
+        .default_width(600.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new(format!("Tick {} of {}..={}", current, oldest, newest))
+                    .color(Color32::WHITE)
+                    .size(20.0),
+            );
+            ui.spacing_mut().slider_width = 560.0;
+            ui.add(egui::Slider::new(&mut target, oldest..=newest).show_value(false));
+            ui.horizontal(|ui| {
+                if ui.button("<<").clicked() {
+                    target = oldest;
+                }
+                if ui.button("<").clicked() {
+                    target = current.saturating_sub(1);
+                }
+                if ui.button(">").clicked() {
+                    target = current + 1;
+                }
+                if ui.button(">>").clicked() {
+                    target = newest;
+                }
+                if ui.button("Resume from here").clicked() {
+                    resume = true;
+                }
+            });
+            if history.cursor().is_some() {
+                ui.label(
+                    egui::RichText::new("Viewing the past: resuming discards later ticks")
+                        .color(Color32::YELLOW),
+                );
+            }
+        });
+
+    if target != current {
+        if let Some((reached, states, deltas)) = history.seek(current, target) {
+            voxel.circuit.rewind(reached, &states, &deltas);
+            apply_changes(&voxel, &mut state_query, states);
+        }
+        controller.mode = SimulationMode::Paused;
+    }
+    if resume {
+        controller.mode = SimulationMode::Running;
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::v_components::TypeVoxel;
+use crate::v_config::{DEFAULT_GATE_DELAY, DEFAULT_WIRE_DELAY};
+
+// Per-world propagation delays. A gate with delay N shows the result of an input change
+// N ticks after the change; a net adds `wire_delay` ticks for every wire voxel it contains.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingModel {
+    pub gate_delays: HashMap<TypeVoxel, u32>,
+    pub wire_delay: f32,
+}
+
+impl Default for TimingModel {
+    fn default() -> Self {
+        TimingModel {
+            gate_delays: HashMap::new(),
+            wire_delay: DEFAULT_WIRE_DELAY,
+        }
+    }
+}
+
+impl TimingModel {
+    pub fn gate_delay(&self, voxel_type: TypeVoxel) -> u64 {
+        self.gate_delays
+            .get(&voxel_type)
+            .copied()
+            .unwrap_or(DEFAULT_GATE_DELAY)
+            .max(1) as u64
+    }
+
+    pub fn set_gate_delay(&mut self, voxel_type: TypeVoxel, delay: u32) {
+        self.gate_delays.insert(voxel_type, delay.max(1));
+    }
+
+    pub fn net_delay(&self, wire_count: usize) -> u64 {
+        (self.wire_delay.max(0.0) * wire_count as f32) as u64
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::{
+    ecs::system::{Res, ResMut, Resource},
+    math::IVec3,
+};
+use bevy_egui::{
+    egui::{self, Color32},
+    EguiContexts,
+};
+use crate::{
+    v_components::{Facing, TypeVoxel, VoxelSettings, WireColor},
+    v_config::{BUS_MAX_WIDTH, DELAY_MAX_TICKS, MEMORY_MAX_ADDRESS_BITS, SCREEN_MAX_SIZE},
+    v_main_menu::WorldName,
+    v_memory::load_memory_file,
+    v_structure::Voxel,
+};
+
+// The voxel whose settings window is open, picked with a middle click. A ROM or RAM window
+// also edits the name of the file it loads and shows how the last load went; a Transmitter or
+// Receiver window edits its channel name.
+#[derive(Resource, Default)]
+pub struct VoxelSettingsPanel {
+    pub position: Option<IVec3>,
+    text_position: Option<IVec3>,
+    text: String,
+    message: String,
+}
+
+impl VoxelSettingsPanel {
+    pub fn new() -> Self {
+        VoxelSettingsPanel::default()
+    }
+}
+
+pub fn voxel_settings_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<VoxelSettingsPanel>,
+    mut voxel: ResMut<Voxel>,
+    world_name: Res<WorldName>,
+) {
+    let Some(position) = panel.position else {
+        return;
+    };
+    if let Some(channel) = voxel.circuit.channel(position).map(str::to_string) {
+        if panel.text_position != Some(position) {
+            panel.text_position = Some(position);
+            panel.text = channel.clone();
+        }
+        let mut open = true;
+        egui::Window::new(format!("Channel at {}", position))
+            .open(&mut open)
+            .resizable(false)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.text_edit_singleline(&mut panel.text);
+                ui.label("Receivers output the OR of every Transmitter on their channel");
+            });
+        if panel.text != channel {
+            voxel.circuit.set_channel(position, panel.text.clone());
+        }
+        if !open {
+            panel.position = None;
+        }
+        return;
+    }
+    // The voxel was removed while its window was open.
+    let Some(mut settings) = voxel.circuit.settings(position) else {
+        panel.position = None;
+        return;
+    };
+
+    // The bus a tap is attached to: in front of a Merger or Adder, behind a Splitter.
+    let forward = voxel.circuit.facing(position).unwrap_or(Facing::East).forward();
+    let bus_value = match voxel.circuit.get(position) {
+        Some((TypeVoxel::Merger | TypeVoxel::Adder, _)) => voxel.circuit.bus_value(position + forward),
+        Some((TypeVoxel::Splitter, _)) => voxel.circuit.bus_value(position - forward),
+        _ => None,
+    };
+
+    if panel.text_position != Some(position) {
+        panel.text_position = Some(position);
+        panel.text = voxel.circuit.memory_file(position).unwrap_or_default().to_string();
+        panel.message.clear();
+    }
+    let mut load_file = false;
+    let panel = panel.as_mut();
+
+    let mut open = true;
+    egui::Window::new(format!("Voxel at {}", position))
+        .open(&mut open)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| match &mut settings {
+            VoxelSettings::Clock { period, phase, duty } => {
+                ui.label(egui::RichText::new("Clock").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(period, 1..=200).text("period (ticks)"));
+                let max_offset = *period;
+                ui.add(egui::Slider::new(phase, 0..=max_offset - 1).text("phase (ticks)"));
+                ui.add(egui::Slider::new(duty, 0..=max_offset).text("high (ticks)"));
+                *phase = (*phase).min(*period - 1);
+                *duty = (*duty).min(*period);
+            }
+            VoxelSettings::Button { hold_ticks } => {
+                ui.label(egui::RichText::new("Button").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(hold_ticks, 0..=200).text("on for (ticks)"));
+                ui.label("0 keeps it on only while held");
+            }
+            VoxelSettings::Delay { ticks } => {
+                ui.label(egui::RichText::new("Delay").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(ticks, 1..=DELAY_MAX_TICKS).text("delay (ticks)"));
+                ui.label("Reads the wire behind it, drives the wire in front");
+            }
+            VoxelSettings::Pulse { ticks } => {
+                ui.label(egui::RichText::new("Pulse extender").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(ticks, 1..=200).text("on for (ticks)"));
+                ui.label("Turns on when the wire behind it turns on, ignoring it until the pulse ends");
+            }
+            VoxelSettings::Tap { bit } => {
+                ui.label(egui::RichText::new("Bus tap").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(bit, 0..=BUS_MAX_WIDTH - 1).text("bit"));
+                ui.label("A Merger drives this bit of the bus in front, a Splitter reads it from the bus behind");
+                if let Some(value) = bus_value {
+                    ui.label(format!("Bus ({} bits): {:#x}", value.width, value.bits));
+                }
+            }
+            VoxelSettings::Width { bits } => {
+                ui.label(egui::RichText::new("Adder").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(bits, 1..=BUS_MAX_WIDTH - 1).text("bits"));
+                ui.label("Adds the buses on its left and right and the carry behind it");
+                if let Some(value) = bus_value {
+                    ui.label(format!("Sum ({} bits): {:#x}", value.width, value.bits));
+                }
+            }
+            VoxelSettings::Wire { color } => {
+                ui.label(egui::RichText::new("Wire").color(Color32::WHITE).size(20.0));
+                ui.horizontal_wrapped(|ui| {
+                    for wire_color in WireColor::ALL {
+                        ui.radio_value(color, wire_color, format!("{:?}", wire_color));
+                    }
+                });
+                ui.label("Only wires of the same colour connect");
+            }
+            VoxelSettings::SevenSegment { decode } => {
+                ui.label(egui::RichText::new("Seven segment").color(Color32::WHITE).size(20.0));
+                ui.checkbox(decode, "Show the low 4 bits as a hex digit");
+                ui.label("Otherwise bits 0-6 drive segments a-g and bit 7 the point");
+            }
+            VoxelSettings::Screen { width, height } => {
+                ui.label(egui::RichText::new("Screen").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(width, 1..=SCREEN_MAX_SIZE).text("width"));
+                ui.add(egui::Slider::new(height, 1..=SCREEN_MAX_SIZE).text("height"));
+                ui.label("Row data behind, row number on the left, clock on top");
+            }
+            VoxelSettings::Memory { address_bits, data_bits } => {
+                ui.label(egui::RichText::new("Memory").color(Color32::WHITE).size(20.0));
+                ui.add(egui::Slider::new(address_bits, 1..=MEMORY_MAX_ADDRESS_BITS).text("address bits"));
+                ui.add(egui::Slider::new(data_bits, 1..=BUS_MAX_WIDTH).text("data bits"));
+                // Enter in the file name loads it as well.
+                let entered = ui
+                    .horizontal(|ui| {
+                        ui.label(format!("assets/Saves/{}/", world_name.0));
+                        let field = ui.text_edit_singleline(&mut panel.text);
+                        field.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                    })
+                    .inner;
+                load_file = ui.button("Load").clicked() || entered;
+                ui.label(".bin is raw little-endian words, Intel HEX starts with ':', other text is hex words");
+                if !panel.message.is_empty() {
+                    ui.label(egui::RichText::new(&panel.message).color(Color32::YELLOW));
+                }
+            }
+        });
+
+    if Some(settings) != voxel.circuit.settings(position) {
+        voxel.circuit.set_settings(position, settings);
+    }
+    if let (true, VoxelSettings::Memory { data_bits, .. }) = (load_file, settings) {
+        panel.message = match load_memory_file(&world_name.0, &panel.text, data_bits) {
+            Ok(words) => {
+                voxel.circuit.set_memory_file(position, panel.text.clone());
+                voxel.circuit.load_memory(position, words);
+                format!("Loaded {} words", voxel.circuit.memory(position).map_or(0, |words| words.len()))
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                e
+            }
+        };
+    }
+    if !open {
+        panel.position = None;
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::{
+    ecs::system::{Local, Res},
+    input::{keyboard::KeyCode, ButtonInput},
+};
+use bevy_egui::{
+    egui::{self, Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, Vec2},
+    EguiContexts,
+};
+use crate::{
+    v_components::Logic,
+    v_config::{WAVEFORM_DEFAULT_TICKS, WAVEFORM_LABEL_WIDTH, WAVEFORM_ROW_HEIGHT},
+    v_probe::{Probe, ProbeRecorder, ProbeValue},
+    v_structure::Voxel,
+};
+
+pub struct WaveformView {
+    visible: bool,
+    ticks_shown: u64,
+    cursor: Option<u64>,
+}
+
+impl Default for WaveformView {
+    fn default() -> Self {
+        WaveformView {
+            visible: false,
+            ticks_shown: WAVEFORM_DEFAULT_TICKS,
+            cursor: None,
+        }
+    }
+}
+
+pub fn waveform_panel(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut view: Local<WaveformView>,
+    recorder: Res<ProbeRecorder>,
+    voxel: Res<Voxel>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyV) && !contexts.ctx_mut().wants_keyboard_input() {
+        view.visible = !view.visible;
+    }
+    if !view.visible {
+        return;
+    }
+
+    let end = voxel.circuit.tick();
+    let start = end.saturating_sub(view.ticks_shown);
+
+    egui::Window::new("Waveforms")
+        .default_width(800.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("-").clicked() {
+                    view.ticks_shown = (view.ticks_shown * 2).min(4096);
+                }
+                if ui.button("+").clicked() {
+                    view.ticks_shown = (view.ticks_shown / 2).max(4);
+                }
+                ui.label(format!("ticks {}..={}", start, end));
+                if let Some(cursor) = view.cursor {
+                    ui.label(egui::RichText::new(format!("cursor @ {}", cursor)).color(Color32::YELLOW));
+                }
+            });
+
+            if recorder.probes.is_empty() {
+                ui.label("No probes. Press X while looking at a voxel to probe it.");
+                return;
+            }
+
+            let size = Vec2::new(
+                ui.available_width(),
+                WAVEFORM_ROW_HEIGHT * recorder.probes.len() as f32,
+            );
+            let (response, painter) = ui.allocate_painter(size, Sense::click_and_drag());
+            let rect = response.rect;
+            let plot = Rect::from_min_max(
+                Pos2::new(rect.left() + WAVEFORM_LABEL_WIDTH, rect.top()),
+                rect.max,
+            );
+            let span = (end - start).max(1) as f32;
+            let tick_x = |tick: u64| plot.left() + (tick.saturating_sub(start)) as f32 / span * plot.width();
+
+            if let Some(pointer) = response.interact_pointer_pos() {
+                if plot.contains(pointer) {
+                    let tick = start + ((pointer.x - plot.left()) / plot.width() * span).round() as u64;
+                    view.cursor = Some(tick.min(end));
+                }
+            }
+            if response.secondary_clicked() {
+                view.cursor = None;
+            }
+
+            for (row, probe) in recorder.probes.iter().enumerate() {
+                let top = rect.top() + row as f32 * WAVEFORM_ROW_HEIGHT;
+                let value = view.cursor.and_then(|cursor| probe.state_at(cursor));
+                let label = match value {
+                    Some(value) => format!("{} = {}", probe.label, value.text()),
+                    None => probe.label.clone(),
+                };
+                painter.text(
+                    Pos2::new(rect.left(), top + WAVEFORM_ROW_HEIGHT / 2.0),
+                    Align2::LEFT_CENTER,
+                    label,
+                    FontId::monospace(12.0),
+                    Color32::WHITE,
+                );
+                draw_trace(&painter, probe, start, end, top, &tick_x);
+            }
+
+            if let Some(cursor) = view.cursor {
+                let x = tick_x(cursor);
+                painter.line_segment(
+                    [Pos2::new(x, plot.top()), Pos2::new(x, plot.bottom())],
+                    Stroke::new(1.0, Color32::YELLOW),
+                );
+            }
+        });
+}
+
+fn draw_trace(
+    painter: &egui::Painter,
+    probe: &Probe,
+    start: u64,
+    end: u64,
+    top: f32,
+    tick_x: &impl Fn(u64) -> f32,
+) {
+    let high = top + 4.0;
+    let low = top + WAVEFORM_ROW_HEIGHT - 4.0;
+    let stroke = Stroke::new(2.0, Color32::LIGHT_GREEN);
+    // Z and X are drawn halfway between low and high.
+    let level = |value: ProbeValue| match value {
+        ProbeValue::Level(Logic::HighZ | Logic::Unknown) => (high + low) / 2.0,
+        value if value.is_on() => high,
+        _ => low,
+    };
+
+    let mut state = probe.state_at(start).unwrap_or(ProbeValue::Level(Logic::Low));
+    let mut x = tick_x(start);
+    for (tick, new_state) in probe.trace().iter().filter(|(tick, _)| *tick > start && *tick <= end) {
+        let next_x = tick_x(*tick);
+        painter.line_segment([Pos2::new(x, level(state)), Pos2::new(next_x, level(state))], stroke);
+        painter.line_segment([Pos2::new(next_x, level(state)), Pos2::new(next_x, level(*new_state))], stroke);
+        state = *new_state;
+        x = next_x;
+    }
+    painter.line_segment([Pos2::new(x, level(state)), Pos2::new(tick_x(end), level(state))], stroke);
+}